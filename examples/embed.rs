@@ -0,0 +1,35 @@
+//! evaluates Plum from Rust: `cargo run --example embed`
+use plum_lang::{Arity, Engine, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+  let mut engine = Engine::new();
+  engine.register("now", Arity::Exact(0), |_, _| {
+    let secs = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs());
+    Ok(Value::Int(secs as i64))
+  });
+  engine.register("repeat", Arity::Exact(2), |_, args| {
+    let n = args.int(1)?.max(0) as usize;
+    Ok(Value::from(args.str(0)?.repeat(n)))
+  });
+  engine.set_global("greeting", Value::from("hello"));
+
+  let src = r#"
+    fun shout(s) { return repeat(s, 3) }
+    var started = now() > 0
+  "#;
+  if let Err(e) = engine.eval_str(src) {
+    eprint!("{}", engine.render(&e, false));
+    return;
+  }
+  let greeting = engine.get_global("greeting").unwrap_or(Value::Nil);
+  match engine.call("shout", &[greeting]) {
+    Ok(v) => println!("{} {:?}", v, engine.get_global("started")),
+    Err(e) => eprint!("{}", engine.render(&e, false)),
+  }
+  if let Err(e) = engine.eval_str("repeat(1, 2)") {
+    eprint!("{}", engine.render(&e, false));
+  }
+}
@@ -1,4 +1,5 @@
 use crate::ast::*;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum Decl {
@@ -6,7 +7,22 @@ pub enum Decl {
   Var(VarDecl),
   Fun(FunDecl),
 }
-impl Node for Decl {}
+impl Node for Decl {
+  fn beg(&self) -> usize {
+    match self {
+      Decl::Bad(x) => x.beg(),
+      Decl::Var(x) => x.beg(),
+      Decl::Fun(x) => x.beg(),
+    }
+  }
+  fn end(&self) -> usize {
+    match self {
+      Decl::Bad(x) => x.end(),
+      Decl::Var(x) => x.end(),
+      Decl::Fun(x) => x.end(),
+    }
+  }
+}
 impl Printable for Decl {
   fn print(&self, l: i32) {
     match self {
@@ -18,12 +34,22 @@ impl Printable for Decl {
 }
 
 #[derive(Debug)]
-pub struct BadDecl {}
-impl Node for BadDecl {}
+pub struct BadDecl {
+  pub from: usize,
+  pub to: usize,
+}
+impl Node for BadDecl {
+  fn beg(&self) -> usize {
+    self.from
+  }
+  fn end(&self) -> usize {
+    self.to
+  }
+}
 impl Printable for BadDecl {
   fn print(&self, l: i32) {
     indent(l);
-    print!("BadDecl \n");
+    println!("BadDecl ");
   }
 }
 
@@ -35,29 +61,59 @@ pub struct VarDecl {
   pub assign_pos: usize,
   pub value: Option<Box<Expr>>,
 }
-impl Node for VarDecl {}
+impl Node for VarDecl {
+  fn beg(&self) -> usize {
+    self.var_pos
+  }
+  fn end(&self) -> usize {
+    if let Option::Some(v) = &self.value {
+      v.end()
+    } else {
+      self.name.end()
+    }
+  }
+}
 impl Printable for VarDecl {
   fn print(&self, l: i32) {
     indent(l);
-    print!("VarDecl<{}, {}> {{\n", self.beg(), self.end());
+    println!("VarDecl<{}, {}> {{", self.beg(), self.end());
     self.name.print(l + 1);
     if let Option::Some(v) = &self.value {
       v.print(l + 1);
     }
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
+/// function declaration
 #[derive(Debug)]
-pub struct FunDecl {}
-impl Node for FunDecl {}
+pub struct FunDecl {
+  pub fun_pos: usize,
+  pub name: Box<Ident>,
+  pub lp_pos: usize,
+  pub params: Vec<Box<Ident>>,
+  pub rp_pos: usize,
+  pub body: Rc<BlockStmt>,
+}
+impl Node for FunDecl {
+  fn beg(&self) -> usize {
+    self.fun_pos
+  }
+  fn end(&self) -> usize {
+    self.body.end()
+  }
+}
 impl Printable for FunDecl {
   fn print(&self, l: i32) {
     indent(l);
-    print!("FunDecl<{}, {}> {{\n", self.beg(), self.end());
-
+    println!("FunDecl<{}, {}> {{", self.beg(), self.end());
+    self.name.print(l + 1);
+    for i in &self.params {
+      i.print(l + 2);
+    }
+    self.body.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
@@ -1,22 +1,70 @@
 use crate::ast::*;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum Expr {
   Bad(BadExpr),
   Ident(Ident),
   BasicLit(BasicLit),
+  Interp(InterpExpr),
+  List(ListLit),
+  Map(MapLit),
+  Fun(FunLit),
+  Index(IndexExpr),
+  Selector(SelectorExpr),
   Call(CallExpr),
   Unary(UnaryExpr),
   Binary(BinaryExpr),
   Paren(ParenExpr),
 }
-impl Node for Expr {}
+impl Node for Expr {
+  fn beg(&self) -> usize {
+    match self {
+      Expr::Bad(x) => x.beg(),
+      Expr::Ident(x) => x.beg(),
+      Expr::BasicLit(x) => x.beg(),
+      Expr::Interp(x) => x.beg(),
+      Expr::List(x) => x.beg(),
+      Expr::Map(x) => x.beg(),
+      Expr::Fun(x) => x.beg(),
+      Expr::Index(x) => x.beg(),
+      Expr::Selector(x) => x.beg(),
+      Expr::Call(x) => x.beg(),
+      Expr::Unary(x) => x.beg(),
+      Expr::Binary(x) => x.beg(),
+      Expr::Paren(x) => x.beg(),
+    }
+  }
+  fn end(&self) -> usize {
+    match self {
+      Expr::Bad(x) => x.end(),
+      Expr::Ident(x) => x.end(),
+      Expr::BasicLit(x) => x.end(),
+      Expr::Interp(x) => x.end(),
+      Expr::List(x) => x.end(),
+      Expr::Map(x) => x.end(),
+      Expr::Fun(x) => x.end(),
+      Expr::Index(x) => x.end(),
+      Expr::Selector(x) => x.end(),
+      Expr::Call(x) => x.end(),
+      Expr::Unary(x) => x.end(),
+      Expr::Binary(x) => x.end(),
+      Expr::Paren(x) => x.end(),
+    }
+  }
+}
 impl Printable for Expr {
   fn print(&self, l: i32) {
     match self {
       Expr::Bad(x) => x.print(l),
       Expr::Ident(x) => x.print(l),
       Expr::BasicLit(x) => x.print(l),
+      Expr::Interp(x) => x.print(l),
+      Expr::List(x) => x.print(l),
+      Expr::Map(x) => x.print(l),
+      Expr::Fun(x) => x.print(l),
+      Expr::Index(x) => x.print(l),
+      Expr::Selector(x) => x.print(l),
       Expr::Call(x) => x.print(l),
       Expr::Unary(x) => x.print(l),
       Expr::Binary(x) => x.print(l),
@@ -26,12 +74,22 @@ impl Printable for Expr {
 }
 
 #[derive(Debug)]
-pub struct BadExpr {}
-impl Node for BadExpr {}
+pub struct BadExpr {
+  pub from: usize,
+  pub to: usize,
+}
+impl Node for BadExpr {
+  fn beg(&self) -> usize {
+    self.from
+  }
+  fn end(&self) -> usize {
+    self.to
+  }
+}
 impl Printable for BadExpr {
   fn print(&self, l: i32) {
     indent(l);
-    print!("BadExpr \n");
+    println!("BadExpr ");
   }
 }
 
@@ -40,24 +98,241 @@ pub struct Ident {
   pub pos: usize,
   pub name: String,
 }
-impl Node for Ident {}
+impl Node for Ident {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    self.pos + self.name.chars().count()
+  }
+}
 impl Printable for Ident {
   fn print(&self, _level: i32) {
     indent(_level);
-    print!("Ident {}\n", self.name);
+    println!("Ident {}", self.name);
   }
 }
 
 #[derive(Debug)]
 pub struct BasicLit {
   pub pos: usize,
+  pub end: usize,
   pub tok: Token,
 }
-impl Node for BasicLit {}
+impl Node for BasicLit {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    self.end
+  }
+}
 impl Printable for BasicLit {
   fn print(&self, _level: i32) {
     indent(_level);
-    print!("BasicLit {:?}\n", self.tok);
+    println!("BasicLit {:?}", self.tok);
+  }
+}
+
+#[derive(Debug)]
+pub enum InterpPart {
+  Str(String),
+  Expr(Box<Expr>),
+}
+
+/// interpolated string literal
+#[derive(Debug)]
+pub struct InterpExpr {
+  pub pos: usize,
+  pub end: usize,
+  pub parts: Vec<InterpPart>,
+}
+impl Node for InterpExpr {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    self.end
+  }
+}
+impl Printable for InterpExpr {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("InterpExpr<{}, {}> {{", self.beg(), self.end());
+    for i in &self.parts {
+      match i {
+        InterpPart::Str(lit) => {
+          indent(l + 1);
+          println!("{:?}", lit);
+        }
+        InterpPart::Expr(x) => x.print(l + 1),
+      }
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// list literal
+#[derive(Debug)]
+pub struct ListLit {
+  pub lbrack: usize,
+  pub elts: Vec<Box<Expr>>,
+  pub rbrack: usize,
+}
+impl Node for ListLit {
+  fn beg(&self) -> usize {
+    self.lbrack
+  }
+  fn end(&self) -> usize {
+    self.rbrack + 1
+  }
+}
+impl Printable for ListLit {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("ListLit<{}, {}> {{", self.beg(), self.end());
+    for i in &self.elts {
+      i.print(l + 1)
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// map literal, a key that is a bare identifier stands for its name
+#[derive(Debug)]
+pub struct MapLit {
+  pub lbrace: usize,
+  pub elts: Vec<KeyValueExpr>,
+  pub rbrace: usize,
+}
+impl Node for MapLit {
+  fn beg(&self) -> usize {
+    self.lbrace
+  }
+  fn end(&self) -> usize {
+    self.rbrace + 1
+  }
+}
+impl Printable for MapLit {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("MapLit<{}, {}> {{", self.beg(), self.end());
+    for i in &self.elts {
+      i.print(l + 1)
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// function literal, `fun(params) { ... }` or `params => expr`
+///
+/// the body of the latter is a block returning `expr`
+#[derive(Debug)]
+pub struct FunLit {
+  pub pos: usize,
+  pub params: Vec<Box<Ident>>,
+  pub body: Rc<BlockStmt>,
+}
+impl Node for FunLit {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    self.body.end()
+  }
+}
+impl Printable for FunLit {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("FunLit<{}, {}> {{", self.beg(), self.end());
+    for i in &self.params {
+      i.print(l + 2);
+    }
+    self.body.print(l + 1);
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// `key: value` in a map literal
+#[derive(Debug)]
+pub struct KeyValueExpr {
+  pub key: Box<Expr>,
+  pub colon: usize,
+  pub value: Box<Expr>,
+}
+impl Node for KeyValueExpr {
+  fn beg(&self) -> usize {
+    self.key.beg()
+  }
+  fn end(&self) -> usize {
+    self.value.end()
+  }
+}
+impl Printable for KeyValueExpr {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("KeyValueExpr<{}, {}> {{", self.beg(), self.end());
+    self.key.print(l + 1);
+    self.value.print(l + 1);
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// `x[index]`
+#[derive(Debug)]
+pub struct IndexExpr {
+  pub x: Box<Expr>,
+  pub lbrack: usize,
+  pub index: Box<Expr>,
+  pub rbrack: usize,
+}
+impl Node for IndexExpr {
+  fn beg(&self) -> usize {
+    self.x.beg()
+  }
+  fn end(&self) -> usize {
+    self.rbrack + 1
+  }
+}
+impl Printable for IndexExpr {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("IndexExpr<{}, {}> {{", self.beg(), self.end());
+    self.x.print(l + 1);
+    self.index.print(l + 1);
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// `x.sel`
+#[derive(Debug)]
+pub struct SelectorExpr {
+  pub x: Box<Expr>,
+  pub period: usize,
+  pub sel: Box<Ident>,
+}
+impl Node for SelectorExpr {
+  fn beg(&self) -> usize {
+    self.x.beg()
+  }
+  fn end(&self) -> usize {
+    self.sel.end()
+  }
+}
+impl Printable for SelectorExpr {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("SelectorExpr<{}, {}> {{", self.beg(), self.end());
+    self.x.print(l + 1);
+    self.sel.print(l + 1);
+    indent(l);
+    println!("}}");
   }
 }
 
@@ -68,36 +343,51 @@ pub struct CallExpr {
   pub args: Vec<Box<Expr>>,
   pub rp_pos: usize,
 }
-impl Node for CallExpr {}
+impl Node for CallExpr {
+  fn beg(&self) -> usize {
+    self.fun.beg()
+  }
+  fn end(&self) -> usize {
+    self.rp_pos + 1
+  }
+}
 impl Printable for CallExpr {
   fn print(&self, l: i32) {
     indent(l);
-    print!("CallExpr<{}, {}> {{\n", self.beg(), self.end());
+    println!("CallExpr<{}, {}> {{", self.beg(), self.end());
     self.fun.print(l + 1);
     for i in &self.args {
       i.print(l + 2)
     }
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
 /// unary expression
 #[derive(Debug)]
 pub struct UnaryExpr {
+  pub op_pos: usize,
   pub op: Token,
   pub x: Box<Expr>,
 }
-impl Node for UnaryExpr {}
+impl Node for UnaryExpr {
+  fn beg(&self) -> usize {
+    self.op_pos
+  }
+  fn end(&self) -> usize {
+    self.x.end()
+  }
+}
 impl Printable for UnaryExpr {
   fn print(&self, l: i32) {
     indent(l);
-    print!("UnaryExpr<{}, {}> {{\n", self.beg(), self.end());
+    println!("UnaryExpr<{}, {}> {{", self.beg(), self.end());
     indent(l + 1);
-    print!("{:?}\n", self.op);
+    println!("{:?}", self.op);
     self.x.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
@@ -105,20 +395,28 @@ impl Printable for UnaryExpr {
 #[derive(Debug)]
 pub struct BinaryExpr {
   pub x: Box<Expr>,
+  pub op_pos: usize,
   pub op: Token,
   pub y: Box<Expr>,
 }
-impl Node for BinaryExpr {}
+impl Node for BinaryExpr {
+  fn beg(&self) -> usize {
+    self.x.beg()
+  }
+  fn end(&self) -> usize {
+    self.y.end()
+  }
+}
 impl Printable for BinaryExpr {
   fn print(&self, l: i32) {
     indent(l);
-    print!("BinaryExpr<{}, {}> {{\n", self.beg(), self.end());
+    println!("BinaryExpr<{}, {}> {{", self.beg(), self.end());
     self.x.print(l + 1);
     indent(l + 1);
-    print!("{:?}\n", self.op);
+    println!("{:?}", self.op);
     self.y.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
@@ -129,13 +427,20 @@ pub struct ParenExpr {
   pub x: Box<Expr>,
   pub r_pos: usize,
 }
-impl Node for ParenExpr {}
+impl Node for ParenExpr {
+  fn beg(&self) -> usize {
+    self.l_pos
+  }
+  fn end(&self) -> usize {
+    self.r_pos + 1
+  }
+}
 impl Printable for ParenExpr {
   fn print(&self, l: i32) {
     indent(l);
-    print!("PareExpr<{}, {}> {{\n", self.beg(), self.end());
+    println!("PareExpr<{}, {}> {{", self.beg(), self.end());
     self.x.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
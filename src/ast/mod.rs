@@ -16,13 +16,11 @@ pub trait Printable {
   fn print(&self, l: i32);
 }
 
+/// `beg` is the position of the first character of a node,
+/// `end` the position right after its last character
 pub trait Node: Debug + Printable {
-  fn beg(&self) -> usize {
-    0
-  }
-  fn end(&self) -> usize {
-    0
-  }
+  fn beg(&self) -> usize;
+  fn end(&self) -> usize;
 }
 
 fn indent(level: i32) {
@@ -37,15 +35,15 @@ pub struct File {
 }
 impl Node for File {
   fn beg(&self) -> usize {
-    if self.stmts.len() > 0 {
+    if !self.stmts.is_empty() {
       self.stmts[0].beg()
     } else {
       0
     }
   }
   fn end(&self) -> usize {
-    if self.stmts.len() > 0 {
-      self.stmts[self.stmts.len() - 1].beg()
+    if !self.stmts.is_empty() {
+      self.stmts[self.stmts.len() - 1].end()
     } else {
       0
     }
@@ -54,12 +52,12 @@ impl Node for File {
 impl Printable for File {
   fn print(&self, l: i32) {
     indent(l);
-    print!("File<{}, {}> {{\n", self.beg(), self.end());
+    println!("File<{}, {}> {{", self.beg(), self.end());
     for i in &self.stmts {
       i.print(l + 1);
     }
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
@@ -6,8 +6,43 @@ pub enum Stmt {
   Decl(DeclStmt),
   Expr(ExprStmt),
   Assign(AssignStmt),
+  Import(ImportStmt),
+  Return(ReturnStmt),
+  Block(BlockStmt),
+  If(IfStmt),
+  While(WhileStmt),
+  Branch(BranchStmt),
+}
+impl Node for Stmt {
+  fn beg(&self) -> usize {
+    match self {
+      Stmt::Bad(x) => x.beg(),
+      Stmt::Decl(x) => x.beg(),
+      Stmt::Expr(x) => x.beg(),
+      Stmt::Assign(x) => x.beg(),
+      Stmt::Import(x) => x.beg(),
+      Stmt::Return(x) => x.beg(),
+      Stmt::Block(x) => x.beg(),
+      Stmt::If(x) => x.beg(),
+      Stmt::While(x) => x.beg(),
+      Stmt::Branch(x) => x.beg(),
+    }
+  }
+  fn end(&self) -> usize {
+    match self {
+      Stmt::Bad(x) => x.end(),
+      Stmt::Decl(x) => x.end(),
+      Stmt::Expr(x) => x.end(),
+      Stmt::Assign(x) => x.end(),
+      Stmt::Import(x) => x.end(),
+      Stmt::Return(x) => x.end(),
+      Stmt::Block(x) => x.end(),
+      Stmt::If(x) => x.end(),
+      Stmt::While(x) => x.end(),
+      Stmt::Branch(x) => x.end(),
+    }
+  }
 }
-impl Node for Stmt {}
 impl Printable for Stmt {
   fn print(&self, l: i32) {
     match self {
@@ -15,34 +50,63 @@ impl Printable for Stmt {
       Stmt::Decl(x) => x.print(l),
       Stmt::Expr(x) => x.print(l),
       Stmt::Assign(x) => x.print(l),
+      Stmt::Import(x) => x.print(l),
+      Stmt::Return(x) => x.print(l),
+      Stmt::Block(x) => x.print(l),
+      Stmt::If(x) => x.print(l),
+      Stmt::While(x) => x.print(l),
+      Stmt::Branch(x) => x.print(l),
     }
   }
 }
 
 /// bad statement
 #[derive(Debug)]
-pub struct BadStmt {}
-impl Node for BadStmt {}
+pub struct BadStmt {
+  pub from: usize,
+  pub to: usize,
+}
+impl Node for BadStmt {
+  fn beg(&self) -> usize {
+    self.from
+  }
+  fn end(&self) -> usize {
+    self.to
+  }
+}
 impl Printable for BadStmt {
   fn print(&self, l: i32) {
     indent(l);
-    print!("BadStmt \n");
+    println!("BadStmt ");
   }
 }
 
 /// declaration statement
 #[derive(Debug)]
 pub struct DeclStmt {
+  /// position of `export`, if the declaration is exported from its module
+  pub export_pos: Option<usize>,
   pub decl: Box<Decl>,
 }
-impl Node for DeclStmt {}
+impl Node for DeclStmt {
+  fn beg(&self) -> usize {
+    self.export_pos.unwrap_or_else(|| self.decl.beg())
+  }
+  fn end(&self) -> usize {
+    self.decl.end()
+  }
+}
 impl Printable for DeclStmt {
   fn print(&self, l: i32) {
     indent(l);
-    print!("DeclStmt<{}, {}> {{\n", self.beg(), self.end());
+    if self.export_pos.is_some() {
+      println!("DeclStmt<{}, {}> export {{", self.beg(), self.end());
+    } else {
+      println!("DeclStmt<{}, {}> {{", self.beg(), self.end());
+    }
     self.decl.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
@@ -51,14 +115,21 @@ impl Printable for DeclStmt {
 pub struct ExprStmt {
   pub x: Box<Expr>,
 }
-impl Node for ExprStmt {}
+impl Node for ExprStmt {
+  fn beg(&self) -> usize {
+    self.x.beg()
+  }
+  fn end(&self) -> usize {
+    self.x.end()
+  }
+}
 impl Printable for ExprStmt {
   fn print(&self, l: i32) {
     indent(l);
-    print!("ExprStmt<{}, {}> {{\n", self.beg(), self.end());
+    println!("ExprStmt<{}, {}> {{", self.beg(), self.end());
     self.x.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
   }
 }
 
@@ -70,14 +141,194 @@ pub struct AssignStmt {
   pub tok: Token,
   pub val: Box<Expr>,
 }
-impl Node for AssignStmt {}
+impl Node for AssignStmt {
+  fn beg(&self) -> usize {
+    self.ptr.beg()
+  }
+  fn end(&self) -> usize {
+    self.val.end()
+  }
+}
 impl Printable for AssignStmt {
   fn print(&self, l: i32) {
     indent(l);
-    print!("AssignStmt<{}, {}> {{\n", self.beg(), self.end());
+    println!("AssignStmt<{}, {}> {{", self.beg(), self.end());
     self.ptr.print(l + 1);
     self.val.print(l + 1);
     indent(l);
-    print!("}}\n");
+    println!("}}");
+  }
+}
+
+/// import statement, `import "path/to/mod"` or `import mod as m`
+#[derive(Debug)]
+pub struct ImportStmt {
+  pub import_pos: usize,
+  /// a string literal or an identifier
+  pub path: Box<Expr>,
+  /// name the module is bound to, by default the name of its file
+  pub alias: Option<Box<Ident>>,
+}
+impl Node for ImportStmt {
+  fn beg(&self) -> usize {
+    self.import_pos
+  }
+  fn end(&self) -> usize {
+    if let Option::Some(v) = &self.alias {
+      v.end()
+    } else {
+      self.path.end()
+    }
+  }
+}
+impl Printable for ImportStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("ImportStmt<{}, {}> {{", self.beg(), self.end());
+    self.path.print(l + 1);
+    if let Option::Some(v) = &self.alias {
+      v.print(l + 1);
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// return statement
+#[derive(Debug)]
+pub struct ReturnStmt {
+  pub pos: usize,
+  pub value: Option<Box<Expr>>,
+}
+impl Node for ReturnStmt {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    if let Option::Some(v) = &self.value {
+      v.end()
+    } else {
+      self.pos + "return".len()
+    }
+  }
+}
+impl Printable for ReturnStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("ReturnStmt<{}, {}> {{", self.beg(), self.end());
+    if let Option::Some(v) = &self.value {
+      v.print(l + 1);
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// block statement
+#[derive(Debug)]
+pub struct BlockStmt {
+  pub lbrace: usize,
+  pub stmts: Vec<Box<Stmt>>,
+  pub rbrace: usize,
+}
+impl Node for BlockStmt {
+  fn beg(&self) -> usize {
+    self.lbrace
+  }
+  fn end(&self) -> usize {
+    self.rbrace + 1
+  }
+}
+impl Printable for BlockStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("BlockStmt<{}, {}> {{", self.beg(), self.end());
+    for i in &self.stmts {
+      i.print(l + 1);
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// if statement
+#[derive(Debug)]
+pub struct IfStmt {
+  pub if_pos: usize,
+  pub cond: Box<Expr>,
+  pub body: Box<BlockStmt>,
+  /// `else` branch, either a block or another if statement
+  pub els: Option<Box<Stmt>>,
+}
+impl Node for IfStmt {
+  fn beg(&self) -> usize {
+    self.if_pos
+  }
+  fn end(&self) -> usize {
+    if let Option::Some(v) = &self.els {
+      v.end()
+    } else {
+      self.body.end()
+    }
+  }
+}
+impl Printable for IfStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("IfStmt<{}, {}> {{", self.beg(), self.end());
+    self.cond.print(l + 1);
+    self.body.print(l + 1);
+    if let Option::Some(v) = &self.els {
+      v.print(l + 1);
+    }
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// while statement
+#[derive(Debug)]
+pub struct WhileStmt {
+  pub while_pos: usize,
+  pub cond: Box<Expr>,
+  pub body: Box<BlockStmt>,
+}
+impl Node for WhileStmt {
+  fn beg(&self) -> usize {
+    self.while_pos
+  }
+  fn end(&self) -> usize {
+    self.body.end()
+  }
+}
+impl Printable for WhileStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("WhileStmt<{}, {}> {{", self.beg(), self.end());
+    self.cond.print(l + 1);
+    self.body.print(l + 1);
+    indent(l);
+    println!("}}");
+  }
+}
+
+/// branch statement, `break` or `continue`
+#[derive(Debug)]
+pub struct BranchStmt {
+  pub pos: usize,
+  pub tok: Token,
+}
+impl Node for BranchStmt {
+  fn beg(&self) -> usize {
+    self.pos
+  }
+  fn end(&self) -> usize {
+    self.pos + self.tok.to_string().len()
+  }
+}
+impl Printable for BranchStmt {
+  fn print(&self, l: i32) {
+    indent(l);
+    println!("BranchStmt<{}, {}> {:?}", self.beg(), self.end(), self.tok);
   }
 }
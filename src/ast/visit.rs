@@ -8,6 +8,12 @@ pub trait Visitor<T> {
   fn visit_decl_stmt(&mut self, x: &DeclStmt) -> T;
   fn visit_expr_stmt(&mut self, x: &ExprStmt) -> T;
   fn visit_assign_stmt(&mut self, x: &AssignStmt) -> T;
+  fn visit_import_stmt(&mut self, x: &ImportStmt) -> T;
+  fn visit_return_stmt(&mut self, x: &ReturnStmt) -> T;
+  fn visit_block_stmt(&mut self, x: &BlockStmt) -> T;
+  fn visit_if_stmt(&mut self, x: &IfStmt) -> T;
+  fn visit_while_stmt(&mut self, x: &WhileStmt) -> T;
+  fn visit_branch_stmt(&mut self, x: &BranchStmt) -> T;
 
   fn visit_decl(&mut self, x: &Decl) -> T;
   fn visit_bad_decl(&mut self, x: &BadDecl) -> T;
@@ -18,6 +24,12 @@ pub trait Visitor<T> {
   fn visit_bad_expr(&mut self, x: &BadExpr) -> T;
   fn visit_ident(&mut self, x: &Ident) -> T;
   fn visit_basic_lit(&mut self, x: &BasicLit) -> T;
+  fn visit_interp_expr(&mut self, x: &InterpExpr) -> T;
+  fn visit_list_lit(&mut self, x: &ListLit) -> T;
+  fn visit_map_lit(&mut self, x: &MapLit) -> T;
+  fn visit_fun_lit(&mut self, x: &FunLit) -> T;
+  fn visit_index_expr(&mut self, x: &IndexExpr) -> T;
+  fn visit_selector_expr(&mut self, x: &SelectorExpr) -> T;
   fn visit_call_expr(&mut self, x: &CallExpr) -> T;
   fn visit_unary_expr(&mut self, x: &UnaryExpr) -> T;
   fn visit_binary_expr(&mut self, x: &BinaryExpr) -> T;
@@ -0,0 +1,473 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// an arbitrary-precision integer
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+  neg: bool,
+  /// magnitude in base 2^32, least significant limb first, without leading zeros
+  mag: Vec<u32>,
+}
+
+impl BigInt {
+  pub fn zero() -> BigInt {
+    BigInt {
+      neg: false,
+      mag: vec![],
+    }
+  }
+
+  fn from_mag(neg: bool, mut mag: Vec<u32>) -> BigInt {
+    while mag.last() == Option::Some(&0) {
+      mag.pop();
+    }
+    // there is no negative zero
+    let neg = neg && !mag.is_empty();
+    BigInt { neg, mag }
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.mag.is_empty()
+  }
+
+  /// parses digits of the given radix, without sign, prefix or separators
+  pub fn parse(digits: &str, radix: u32) -> Option<BigInt> {
+    if digits.is_empty() {
+      return Option::None;
+    }
+    let mut mag = vec![];
+    for c in digits.chars() {
+      let d = c.to_digit(radix)?;
+      mul_small(&mut mag, radix);
+      add_small(&mut mag, d);
+    }
+    Option::Some(BigInt::from_mag(false, mag))
+  }
+
+  /// `10^n`
+  pub fn pow10(n: u32) -> BigInt {
+    let mut mag = vec![1];
+    for _ in 0..n {
+      mul_small(&mut mag, 10);
+    }
+    BigInt::from_mag(false, mag)
+  }
+
+  /// the integer part of a finite float, exactly
+  pub fn from_f64(f: f64) -> Option<BigInt> {
+    if !f.is_finite() {
+      return Option::None;
+    }
+    let f = f.trunc();
+    if f.abs() < 9_223_372_036_854_775_808.0 {
+      return Option::Some(BigInt::from(f as i64));
+    }
+    // beyond 2^63 the float is `mantissa * 2^exp` with a positive exp
+    let bits = f.to_bits();
+    let exp = ((bits >> 52) & 0x7ff) as usize - 1075;
+    let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+    let mut mag = vec![0; exp / 32];
+    let shifted = (mantissa as u128) << (exp % 32);
+    mag.extend(&[
+      shifted as u32,
+      (shifted >> 32) as u32,
+      (shifted >> 64) as u32,
+    ]);
+    Option::Some(BigInt::from_mag(f < 0.0, mag))
+  }
+
+  pub fn to_i64(&self) -> Option<i64> {
+    if self.mag.len() > 2 {
+      return Option::None;
+    }
+    let m = self
+      .mag
+      .iter()
+      .rev()
+      .fold(0u64, |acc, &limb| (acc << 32) | limb as u64);
+    if self.neg {
+      0i64.checked_sub_unsigned(m)
+    } else {
+      i64::try_from(m).ok()
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    // good enough for a conversion that is lossy anyway
+    let m = self
+      .mag
+      .iter()
+      .rev()
+      .fold(0.0, |acc, &limb| acc * 4_294_967_296.0 + limb as f64);
+    if self.neg {
+      -m
+    } else {
+      m
+    }
+  }
+
+  /// quotient truncated toward zero and remainder with the sign of `self`,
+  /// `None` when dividing by zero
+  pub fn div_rem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+    if rhs.is_zero() {
+      return Option::None;
+    }
+    let (q, r) = div_rem_mag(&self.mag, &rhs.mag);
+    Option::Some((
+      BigInt::from_mag(self.neg != rhs.neg, q),
+      BigInt::from_mag(self.neg, r),
+    ))
+  }
+}
+
+impl From<i64> for BigInt {
+  fn from(i: i64) -> BigInt {
+    let m = i.unsigned_abs();
+    BigInt::from_mag(i < 0, vec![m as u32, (m >> 32) as u32])
+  }
+}
+
+impl Ord for BigInt {
+  fn cmp(&self, other: &BigInt) -> Ordering {
+    match (self.neg, other.neg) {
+      (false, true) => Ordering::Greater,
+      (true, false) => Ordering::Less,
+      (false, false) => cmp_mag(&self.mag, &other.mag),
+      (true, true) => cmp_mag(&other.mag, &self.mag),
+    }
+  }
+}
+
+impl PartialOrd for BigInt {
+  fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+    Option::Some(self.cmp(other))
+  }
+}
+
+impl Neg for &BigInt {
+  type Output = BigInt;
+  fn neg(self) -> BigInt {
+    BigInt::from_mag(!self.neg, self.mag.clone())
+  }
+}
+
+impl Add for &BigInt {
+  type Output = BigInt;
+  fn add(self, rhs: &BigInt) -> BigInt {
+    if self.neg == rhs.neg {
+      return BigInt::from_mag(self.neg, add_mag(&self.mag, &rhs.mag));
+    }
+    // opposite signs, subtract the smaller magnitude from the larger one
+    match cmp_mag(&self.mag, &rhs.mag) {
+      Ordering::Less => BigInt::from_mag(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+      _ => BigInt::from_mag(self.neg, sub_mag(&self.mag, &rhs.mag)),
+    }
+  }
+}
+
+impl Sub for &BigInt {
+  type Output = BigInt;
+  fn sub(self, rhs: &BigInt) -> BigInt {
+    self + &-rhs
+  }
+}
+
+impl Mul for &BigInt {
+  type Output = BigInt;
+  fn mul(self, rhs: &BigInt) -> BigInt {
+    let mut mag = vec![0u32; self.mag.len() + rhs.mag.len()];
+    for (i, &a) in self.mag.iter().enumerate() {
+      let mut carry = 0u64;
+      for (j, &b) in rhs.mag.iter().enumerate() {
+        let t = a as u64 * b as u64 + mag[i + j] as u64 + carry;
+        mag[i + j] = t as u32;
+        carry = t >> 32;
+      }
+      mag[i + rhs.mag.len()] = carry as u32;
+    }
+    BigInt::from_mag(self.neg != rhs.neg, mag)
+  }
+}
+
+impl fmt::Display for BigInt {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.is_zero() {
+      return write!(f, "0");
+    }
+    // peel off 9 decimal digits at a time
+    let mut mag = self.mag.clone();
+    let mut chunks = vec![];
+    while !mag.is_empty() {
+      chunks.push(div_small(&mut mag, 1_000_000_000));
+    }
+    if self.neg {
+      write!(f, "-")?;
+    }
+    write!(f, "{}", chunks.pop().unwrap_or(0))?;
+    for chunk in chunks.iter().rev() {
+      write!(f, "{:09}", chunk)?;
+    }
+    Ok(())
+  }
+}
+
+fn trim(mag: &mut Vec<u32>) {
+  while mag.last() == Option::Some(&0) {
+    mag.pop();
+  }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+  a.len()
+    .cmp(&b.len())
+    .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let (a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+  let mut mag = Vec::with_capacity(a.len() + 1);
+  let mut carry = 0u64;
+  for (i, &x) in a.iter().enumerate() {
+    let t = x as u64 + b.get(i).copied().unwrap_or(0) as u64 + carry;
+    mag.push(t as u32);
+    carry = t >> 32;
+  }
+  mag.push(carry as u32);
+  mag
+}
+
+/// `a - b`, `a` must not be smaller than `b`
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut mag = Vec::with_capacity(a.len());
+  let mut borrow = 0i64;
+  for (i, &x) in a.iter().enumerate() {
+    let mut t = x as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+    borrow = 0;
+    if t < 0 {
+      t += 1 << 32;
+      borrow = 1;
+    }
+    mag.push(t as u32);
+  }
+  mag
+}
+
+fn mul_small(mag: &mut Vec<u32>, m: u32) {
+  let mut carry = 0u64;
+  for limb in mag.iter_mut() {
+    let t = *limb as u64 * m as u64 + carry;
+    *limb = t as u32;
+    carry = t >> 32;
+  }
+  if carry != 0 {
+    mag.push(carry as u32);
+  }
+}
+
+fn add_small(mag: &mut Vec<u32>, a: u32) {
+  let mut carry = a as u64;
+  for limb in mag.iter_mut() {
+    if carry == 0 {
+      return;
+    }
+    let t = *limb as u64 + carry;
+    *limb = t as u32;
+    carry = t >> 32;
+  }
+  if carry != 0 {
+    mag.push(carry as u32);
+  }
+}
+
+/// divides in place and returns the remainder
+fn div_small(mag: &mut Vec<u32>, d: u32) -> u32 {
+  let mut rem = 0u64;
+  for limb in mag.iter_mut().rev() {
+    let t = (rem << 32) | *limb as u64;
+    *limb = (t / d as u64) as u32;
+    rem = t % d as u64;
+  }
+  trim(mag);
+  rem as u32
+}
+
+/// schoolbook long division, one bit at a time
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+  if b.len() == 1 {
+    let mut q = a.to_vec();
+    let r = div_small(&mut q, b[0]);
+    return (q, vec![r]);
+  }
+  let mut q = vec![0u32; a.len()];
+  let mut r: Vec<u32> = vec![];
+  for i in (0..a.len() * 32).rev() {
+    // r = r << 1 | bit i of a
+    let mut carry = (a[i / 32] >> (i % 32)) & 1;
+    for limb in r.iter_mut() {
+      let top = *limb >> 31;
+      *limb = (*limb << 1) | carry;
+      carry = top;
+    }
+    if carry != 0 {
+      r.push(carry);
+    }
+    if cmp_mag(&r, b) != Ordering::Less {
+      r = sub_mag(&r, b);
+      trim(&mut r);
+      q[i / 32] |= 1 << (i % 32);
+    }
+  }
+  (q, r)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// parses a decimal int with an optional `-`
+  fn big(s: &str) -> BigInt {
+    match s.strip_prefix('-') {
+      Option::Some(digits) => -&BigInt::parse(digits, 10).unwrap(),
+      Option::None => BigInt::parse(s, 10).unwrap(),
+    }
+  }
+
+  /// values around the limb boundaries, and the i128 arithmetic checks them against
+  fn samples() -> Vec<i128> {
+    let mut ret = vec![0, 1, 3, 7, 1_000_000_007];
+    for bits in &[31, 32, 33, 63, 64, 65, 95, 96, 97, 126] {
+      let p = 1i128 << bits;
+      ret.extend(&[p - 1, p, p + 1, p + 12_345]);
+    }
+    ret.push(i128::MAX);
+    let neg: Vec<i128> = ret.iter().map(|v| -v).collect();
+    ret.extend(neg);
+    ret
+  }
+
+  #[test]
+  fn parse_and_display() {
+    let cases = [
+      "0",
+      "4294967295",
+      "4294967296",
+      "18446744073709551615",
+      "18446744073709551616",
+      "1000000000",
+      "999999999999999999999999999",
+      "1000000000000000000000000000000000000001",
+      "-79228162514264337593543950336",
+    ];
+    for s in &cases {
+      assert_eq!(big(s).to_string(), *s);
+    }
+    assert_eq!(big("000123").to_string(), "123");
+    assert_eq!(big("-0").to_string(), "0");
+    assert_eq!(
+      BigInt::parse("ffffffffffffffffffffffff", 16)
+        .unwrap()
+        .to_string(),
+      "79228162514264337593543950335"
+    );
+    assert_eq!(BigInt::parse("102", 2), Option::None);
+    assert_eq!(BigInt::parse("", 10), Option::None);
+    assert_eq!(BigInt::pow10(20).to_string(), "100000000000000000000");
+  }
+
+  #[test]
+  fn to_i64_at_the_limits() {
+    assert_eq!(big("9223372036854775807").to_i64(), Option::Some(i64::MAX));
+    assert_eq!(big("-9223372036854775808").to_i64(), Option::Some(i64::MIN));
+    assert_eq!(big("9223372036854775808").to_i64(), Option::None);
+    assert_eq!(big("-9223372036854775809").to_i64(), Option::None);
+    assert_eq!(big("18446744073709551615").to_i64(), Option::None);
+    assert_eq!(big("18446744073709551616").to_i64(), Option::None);
+    assert_eq!(big("-4294967296").to_i64(), Option::Some(-4_294_967_296));
+    assert_eq!(BigInt::zero().to_i64(), Option::Some(0));
+    for &i in &[i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX] {
+      assert_eq!(BigInt::from(i).to_i64(), Option::Some(i));
+      assert_eq!(BigInt::from(i).to_string(), i.to_string());
+    }
+  }
+
+  #[test]
+  fn from_f64_is_exact() {
+    let cases = [
+      9_223_372_036_854_775_808.0,
+      -9_223_372_036_854_775_808.0,
+      18_446_744_073_709_551_616.0,
+      18_446_744_073_709_555_712.0,
+      1e20,
+      -1.5e19,
+      123_456_789.0 * 4_294_967_296.0 * 4_294_967_296.0,
+      1e300,
+      f64::MAX,
+      -f64::MAX,
+      9_223_372_036_854_774_784.0,
+    ];
+    for &f in &cases {
+      let i = BigInt::from_f64(f).unwrap();
+      assert_eq!(i.to_string(), format!("{:.0}", f));
+      assert_eq!(i.to_f64(), f);
+    }
+    assert_eq!(BigInt::from_f64(-0.9), Option::Some(BigInt::zero()));
+    assert_eq!(BigInt::from_f64(2.5).unwrap().to_string(), "2");
+    assert_eq!(BigInt::from_f64(f64::NAN), Option::None);
+    assert_eq!(BigInt::from_f64(f64::INFINITY), Option::None);
+  }
+
+  #[test]
+  fn arithmetic_across_limbs() {
+    let samples = samples();
+    for &a in &samples {
+      for &b in &samples {
+        let (x, y) = (big(&a.to_string()), big(&b.to_string()));
+        assert_eq!(x.cmp(&y), a.cmp(&b), "{} <=> {}", a, b);
+        if let Option::Some(s) = a.checked_add(b) {
+          assert_eq!((&x + &y).to_string(), s.to_string(), "{} + {}", a, b);
+        }
+        if let Option::Some(d) = a.checked_sub(b) {
+          assert_eq!((&x - &y).to_string(), d.to_string(), "{} - {}", a, b);
+        }
+        if let Option::Some(p) = a.checked_mul(b) {
+          assert_eq!((&x * &y).to_string(), p.to_string(), "{} * {}", a, b);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn division_truncates_and_remainder_follows_the_dividend() {
+    let samples = samples();
+    for &a in &samples {
+      for &b in &samples {
+        let (x, y) = (big(&a.to_string()), big(&b.to_string()));
+        if b == 0 {
+          assert_eq!(x.div_rem(&y), Option::None);
+          continue;
+        }
+        let (q, r) = x.div_rem(&y).unwrap();
+        assert_eq!(q.to_string(), (a / b).to_string(), "{} / {}", a, b);
+        assert_eq!(r.to_string(), (a % b).to_string(), "{} % {}", a, b);
+      }
+    }
+  }
+
+  #[test]
+  fn division_beyond_128_bits() {
+    let a = big("100000000000000000000000000000000000000000000000000000007");
+    let b = big("340282366920938463463374607431768211457");
+    let r = big("12345678901234567890");
+    let n = &(&a * &b) + &r;
+    assert_eq!(n.div_rem(&b), Option::Some((a.clone(), r.clone())));
+    assert_eq!(n.div_rem(&a), Option::Some((b.clone(), r.clone())));
+    assert_eq!(n.div_rem(&-&b), Option::Some((-&a, r.clone())));
+    assert_eq!((-&n).div_rem(&b), Option::Some((-&a, -&r)));
+    assert_eq!((-&n).div_rem(&-&b), Option::Some((a.clone(), -&r)));
+    // by itself and by a larger magnitude
+    assert_eq!(n.div_rem(&n), Option::Some((big("1"), BigInt::zero())));
+    assert_eq!(r.div_rem(&n), Option::Some((BigInt::zero(), r)));
+  }
+}
@@ -0,0 +1,154 @@
+use crate::bignum::BigInt;
+use crate::error::*;
+use crate::interpreter::Interpreter;
+use crate::map::Key;
+use crate::native::{Args, Arity};
+use crate::value::Value;
+
+/// registers the functions every program can call
+pub fn register(interp: &mut Interpreter) {
+  interp.register("print", Arity::Any, print);
+  interp.register("println", Arity::Any, println);
+  interp.register("int", Arity::Exact(1), int);
+  interp.register("float", Arity::Exact(1), float);
+  interp.register("len", Arity::Exact(1), len);
+  interp.register("push", Arity::Exact(2), push);
+  interp.register("pop", Arity::Exact(1), pop);
+  interp.register("slice", Arity::Exact(3), slice);
+  interp.register("map", Arity::Exact(2), |interp, args| {
+    map_filter(interp, args, true)
+  });
+  interp.register("filter", Arity::Exact(2), |interp, args| {
+    map_filter(interp, args, false)
+  });
+  interp.register("keys", Arity::Exact(1), |_, args| keys_values(args, true));
+  interp.register("values", Arity::Exact(1), |_, args| {
+    keys_values(args, false)
+  });
+  interp.register("has", Arity::Exact(2), has);
+  interp.register("delete", Arity::Exact(2), delete);
+}
+
+fn print(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  for i in args.values() {
+    print!("{} ", i);
+  }
+  Ok(Value::Nil)
+}
+
+fn println(interp: &mut Interpreter, args: &Args) -> Result<Value> {
+  print(interp, args)?;
+  println!();
+  Ok(Value::Nil)
+}
+
+fn int(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  match args.get(0)? {
+    v @ Value::Int(_) | v @ Value::BigInt(_) => Ok(v.clone()),
+    Value::Float(f) => BigInt::from_f64(*f).map(Value::from).ok_or_else(|| {
+      RuntimeError::unlocated(ErrorKind::Overflow, format!("cannot convert {} to int", f))
+    }),
+    Value::Decimal(d) => Ok(Value::from(d.trunc())),
+    v => Err(RuntimeError::unlocated(
+      ErrorKind::Type,
+      format!("cannot convert {} to int", v.type_name()),
+    )),
+  }
+}
+
+fn float(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  let v = args.get(0)?;
+  match v.to_f64() {
+    Option::Some(f) => Ok(Value::Float(f)),
+    Option::None => Err(RuntimeError::unlocated(
+      ErrorKind::Type,
+      format!("cannot convert {} to float", v.type_name()),
+    )),
+  }
+}
+
+fn len(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  match args.get(0)? {
+    Value::Str(s) => Ok(Value::Int(s.chars().count() as i64)),
+    Value::List(l) => Ok(Value::Int(l.borrow().len() as i64)),
+    Value::Map(m) => Ok(Value::Int(m.borrow().len() as i64)),
+    _ => Err(args.error(0, "a list, a map or a string")),
+  }
+}
+
+fn push(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  args.list(0)?.borrow_mut().push(args.get(1)?.clone());
+  Ok(Value::Nil)
+}
+
+fn pop(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  args.list(0)?.borrow_mut().pop().ok_or_else(|| {
+    RuntimeError::unlocated(ErrorKind::Index, String::from("pop from an empty list"))
+  })
+}
+
+fn slice(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  let l = args.list(0)?;
+  let l = l.borrow();
+  let (from, to) = (args.int(1)?, args.int(2)?);
+  if from < 0 || from > to || to > l.len() as i64 {
+    return Err(RuntimeError::unlocated(
+      ErrorKind::Index,
+      format!(
+        "slice [{}:{}] out of range for a list of length {}",
+        from,
+        to,
+        l.len()
+      ),
+    ));
+  }
+  Ok(Value::from(l[from as usize..to as usize].to_vec()))
+}
+
+fn map_filter(interp: &mut Interpreter, args: &Args, map: bool) -> Result<Value> {
+  // a copy, `f` may change the list while we walk it
+  let l = args.list(0)?.borrow().clone();
+  let f = args.fun(1)?;
+  let mut ret = vec![];
+  for v in l {
+    let r = interp.call_value(&f, vec![v.clone()])?;
+    if map {
+      ret.push(r);
+    } else if r.truthy() {
+      ret.push(v);
+    }
+  }
+  Ok(Value::from(ret))
+}
+
+fn keys_values(args: &Args, keys: bool) -> Result<Value> {
+  let m = args.map(0)?;
+  let m = m.borrow();
+  let l: Vec<Value> = if keys {
+    m.iter().map(|(k, _)| k.to_value()).collect()
+  } else {
+    m.iter().map(|(_, v)| v.clone()).collect()
+  };
+  Ok(Value::from(l))
+}
+
+fn has(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  let m = args.map(0)?;
+  let has = m.borrow().get(&key(args.get(1)?)?).is_some();
+  Ok(Value::Bool(has))
+}
+
+fn delete(_: &mut Interpreter, args: &Args) -> Result<Value> {
+  let m = args.map(0)?;
+  let old = m.borrow_mut().remove(&key(args.get(1)?)?);
+  Ok(old.unwrap_or(Value::Nil))
+}
+
+fn key(v: &Value) -> Result<Key> {
+  Key::from_value(v).ok_or_else(|| {
+    RuntimeError::unlocated(
+      ErrorKind::Type,
+      format!("{} cannot be a map key", v.type_name()),
+    )
+  })
+}
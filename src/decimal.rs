@@ -0,0 +1,214 @@
+use crate::bignum::BigInt;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// digits kept after the point by a division that does not end
+pub const DIV_SCALE: u32 = 20;
+
+/// a literal cannot have more digits after the point than this, nor be
+/// scaled by a larger power of ten
+pub const MAX_LIT_SCALE: i64 = 1000;
+
+/// an exact decimal number, `unscaled * 10^-scale`
+#[derive(Debug, Clone)]
+pub struct Decimal {
+  unscaled: BigInt,
+  scale: u32,
+}
+
+impl Decimal {
+  /// parses a decimal literal like `1.10` or `2.5e-3`, without the `d` suffix,
+  /// `None` if it is invalid or its scale is beyond `MAX_LIT_SCALE`
+  pub fn parse(lit: &str) -> Option<Decimal> {
+    let lit = lit.replace('_', "");
+    let (num, exp) = match lit.find(['e', 'E']) {
+      Option::Some(i) => (&lit[..i], lit[i + 1..].parse::<i64>().ok()?),
+      Option::None => (lit.as_str(), 0),
+    };
+    let (int, frac) = match num.find('.') {
+      Option::Some(i) => (&num[..i], &num[i + 1..]),
+      Option::None => (num, ""),
+    };
+    let unscaled = BigInt::parse(&[int, frac].concat(), 10)?;
+    let scale = (frac.len() as i64).checked_sub(exp)?;
+    if scale.abs() > MAX_LIT_SCALE {
+      return Option::None;
+    }
+    if scale < 0 {
+      let unscaled = &unscaled * &BigInt::pow10(-scale as u32);
+      return Option::Some(Decimal { unscaled, scale: 0 });
+    }
+    Option::Some(Decimal {
+      unscaled,
+      scale: scale as u32,
+    })
+  }
+
+  /// the value with `scale` digits after the point, `scale` must not be smaller
+  fn rescale(&self, scale: u32) -> BigInt {
+    &self.unscaled * &BigInt::pow10(scale - self.scale)
+  }
+
+  /// both values with as many digits after the point
+  fn align(&self, other: &Decimal) -> (BigInt, BigInt, u32) {
+    let scale = self.scale.max(other.scale);
+    (self.rescale(scale), other.rescale(scale), scale)
+  }
+
+  /// the integer part
+  pub fn trunc(&self) -> BigInt {
+    match self.unscaled.div_rem(&BigInt::pow10(self.scale)) {
+      Option::Some((q, _)) => q,
+      Option::None => BigInt::zero(),
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.to_string().parse().unwrap_or(f64::NAN)
+  }
+
+  /// the quotient with at least `DIV_SCALE` digits after the point, truncated,
+  /// and then without trailing zeros beyond the scale of the operands;
+  /// `None` when dividing by zero
+  pub fn checked_div(&self, rhs: &Decimal) -> Option<Decimal> {
+    let scale = self.scale.max(rhs.scale) + DIV_SCALE;
+    // self * 10^scale / rhs keeps `scale` digits
+    let num = self.rescale(scale + rhs.scale);
+    let (q, _) = num.div_rem(&rhs.unscaled)?;
+    let mut d = Decimal { unscaled: q, scale };
+    let ten = BigInt::from(10);
+    while d.scale > self.scale.max(rhs.scale) {
+      match d.unscaled.div_rem(&ten) {
+        Option::Some((q, r)) if r.is_zero() => {
+          d.unscaled = q;
+          d.scale -= 1;
+        }
+        _ => break,
+      }
+    }
+    Option::Some(d)
+  }
+
+  /// the remainder of the truncated division, `None` when dividing by zero
+  pub fn checked_rem(&self, rhs: &Decimal) -> Option<Decimal> {
+    let (a, b, scale) = self.align(rhs);
+    let (_, r) = a.div_rem(&b)?;
+    Option::Some(Decimal { unscaled: r, scale })
+  }
+}
+
+impl From<BigInt> for Decimal {
+  fn from(i: BigInt) -> Decimal {
+    Decimal {
+      unscaled: i,
+      scale: 0,
+    }
+  }
+}
+
+impl PartialEq for Decimal {
+  // `1.10` and `1.1` are the same number
+  fn eq(&self, other: &Decimal) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+  fn cmp(&self, other: &Decimal) -> Ordering {
+    let (a, b, _) = self.align(other);
+    a.cmp(&b)
+  }
+}
+
+impl PartialOrd for Decimal {
+  fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+    Option::Some(self.cmp(other))
+  }
+}
+
+impl Neg for &Decimal {
+  type Output = Decimal;
+  fn neg(self) -> Decimal {
+    Decimal {
+      unscaled: -&self.unscaled,
+      scale: self.scale,
+    }
+  }
+}
+
+impl Add for &Decimal {
+  type Output = Decimal;
+  fn add(self, rhs: &Decimal) -> Decimal {
+    let (a, b, scale) = self.align(rhs);
+    Decimal {
+      unscaled: &a + &b,
+      scale,
+    }
+  }
+}
+
+impl Sub for &Decimal {
+  type Output = Decimal;
+  fn sub(self, rhs: &Decimal) -> Decimal {
+    let (a, b, scale) = self.align(rhs);
+    Decimal {
+      unscaled: &a - &b,
+      scale,
+    }
+  }
+}
+
+impl Mul for &Decimal {
+  type Output = Decimal;
+  fn mul(self, rhs: &Decimal) -> Decimal {
+    Decimal {
+      unscaled: &self.unscaled * &rhs.unscaled,
+      scale: self.scale + rhs.scale,
+    }
+  }
+}
+
+impl fmt::Display for Decimal {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let digits = self.unscaled.to_string();
+    let (sign, digits) = match digits.strip_prefix('-') {
+      Option::Some(d) => ("-", d),
+      Option::None => ("", digits.as_str()),
+    };
+    let scale = self.scale as usize;
+    if scale == 0 {
+      return write!(f, "{}{}", sign, digits);
+    }
+    // pad so that there is at least one digit before the point
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    write!(f, "{}{}.{}", sign, int, frac)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(lit: &str) -> String {
+    Decimal::parse(lit).unwrap().to_string()
+  }
+
+  #[test]
+  fn parses_literals() {
+    assert_eq!(parse("1.10"), "1.10");
+    assert_eq!(parse("2.5e-3"), "0.0025");
+    assert_eq!(parse("1_000.5e2"), "100050");
+  }
+
+  #[test]
+  fn rejects_huge_exponents() {
+    assert!(Decimal::parse("1e-2147483648").is_none());
+    assert!(Decimal::parse("1e-9223372036854775808").is_none());
+    assert!(Decimal::parse("1e99999999").is_none());
+    assert!(Decimal::parse("1e1000").is_some());
+  }
+}
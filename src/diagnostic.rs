@@ -0,0 +1,173 @@
+use crate::error::{ErrorKind, RuntimeError};
+use crate::source::SourceMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  Error,
+  Warning,
+}
+
+/// a message about a span of source code, rendered like rustc does
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub level: Level,
+  pub code: Option<&'static str>,
+  pub msg: String,
+  pub beg: usize,
+  pub end: usize,
+  pub notes: Vec<String>,
+  pub help: Vec<String>,
+}
+
+impl Diagnostic {
+  pub fn error(msg: String, beg: usize, end: usize) -> Diagnostic {
+    Diagnostic {
+      level: Level::Error,
+      code: Option::None,
+      msg,
+      beg,
+      end,
+      notes: vec![],
+      help: vec![],
+    }
+  }
+
+  pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+    self.code = Option::Some(code);
+    self
+  }
+
+  pub fn with_note(mut self, note: String) -> Diagnostic {
+    self.notes.push(note);
+    self
+  }
+
+  pub fn with_help(mut self, help: String) -> Diagnostic {
+    self.help.push(help);
+    self
+  }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+  fn from(e: &RuntimeError) -> Diagnostic {
+    let d = Diagnostic::error(e.msg.clone(), e.pos, e.end).with_code(e.kind.code());
+    match e.kind {
+      ErrorKind::UndefinedVariable => d.with_help(String::from(
+        "declare it with `var` or `fun` before using it",
+      )),
+      ErrorKind::ImportCycle => d.with_help(String::from(
+        "move the declarations both modules need into a module of their own",
+      )),
+      _ => d,
+    }
+  }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// renders diagnostics, with ANSI colors or as plain text
+pub struct Renderer {
+  color: bool,
+}
+
+impl Renderer {
+  pub fn new(color: bool) -> Renderer {
+    Renderer { color }
+  }
+
+  fn paint(&self, style: &str, s: &str) -> String {
+    if self.color {
+      format!("{}{}{}", style, s, RESET)
+    } else {
+      String::from(s)
+    }
+  }
+
+  pub fn render(&self, source_map: &SourceMap, d: &Diagnostic) -> String {
+    let (level, style) = match d.level {
+      Level::Error => ("error", RED),
+      Level::Warning => ("warning", YELLOW),
+    };
+    let header = match d.code {
+      Option::Some(code) => format!("{}[{}]", level, code),
+      Option::None => String::from(level),
+    };
+    let mut out = String::new();
+    let _ = writeln!(
+      out,
+      "{}{}",
+      self.paint(style, &header),
+      self.paint(BOLD, &format!(": {}", d.msg))
+    );
+
+    let file = match source_map.file(d.beg) {
+      Option::Some(f) => f,
+      Option::None => {
+        self.render_footer(&mut out, d, "");
+        return out;
+      }
+    };
+    let (line, col) = file.line_col(d.beg);
+    let text = file.src.lines().nth(line - 1).unwrap_or("");
+    let gutter = " ".repeat(line.to_string().len());
+    let bar = self.paint(BLUE, "|");
+
+    let _ = writeln!(
+      out,
+      "{}{} {}:{}:{}",
+      gutter,
+      self.paint(BLUE, "-->"),
+      file.name,
+      line,
+      col
+    );
+    let _ = writeln!(out, "{} {}", gutter, bar);
+    let _ = writeln!(
+      out,
+      "{} {} {}",
+      self.paint(BLUE, &line.to_string()),
+      bar,
+      text
+    );
+
+    // underline up to the end of the span or of the line, at least one caret
+    let width = text.chars().count();
+    let from = (col - 1).min(width);
+    let to = if d.end > d.beg && source_map.position(d.end).line == line {
+      file.line_col(d.end).1 - 1
+    } else {
+      width
+    };
+    // keep tabs so the carets line up with the text above
+    let pad: String = text
+      .chars()
+      .take(from)
+      .map(|c| if c == '\t' { '\t' } else { ' ' })
+      .collect();
+    let carets = "^".repeat(to.saturating_sub(from).max(1));
+    let _ = writeln!(
+      out,
+      "{} {} {}{}",
+      gutter,
+      bar,
+      pad,
+      self.paint(style, &carets)
+    );
+    self.render_footer(&mut out, d, &gutter);
+    out
+  }
+
+  fn render_footer(&self, out: &mut String, d: &Diagnostic, gutter: &str) {
+    for note in &d.notes {
+      let _ = writeln!(out, "{} {} note: {}", gutter, self.paint(BLUE, "="), note);
+    }
+    for help in &d.help {
+      let _ = writeln!(out, "{} {} help: {}", gutter, self.paint(BLUE, "="), help);
+    }
+  }
+}
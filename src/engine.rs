@@ -0,0 +1,212 @@
+use crate::ast::Visitor;
+use crate::diagnostic::{Diagnostic, Renderer};
+use crate::error::{ErrorKind, RuntimeError};
+use crate::interpreter::Interpreter;
+use crate::native::{Args, Arity};
+use crate::value::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// why evaluating a program failed
+#[derive(Debug)]
+pub enum Error {
+  /// the file cannot be read
+  Io(PathBuf, io::Error),
+  /// the syntax errors of the program and their positions
+  Syntax(Vec<(usize, String)>),
+  Runtime(RuntimeError),
+}
+
+impl Error {
+  /// the diagnostics to show for this error, see `Engine::render`
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    match self {
+      Error::Io(..) => vec![Diagnostic::error(self.to_string(), 0, 0)],
+      Error::Syntax(errors) => syntax_diagnostics(errors),
+      // an imported module that does not parse
+      Error::Runtime(e) if !e.syntax.is_empty() => syntax_diagnostics(&e.syntax),
+      Error::Runtime(e) => vec![Diagnostic::from(e)],
+    }
+  }
+}
+
+fn syntax_diagnostics(errors: &[(usize, String)]) -> Vec<Diagnostic> {
+  errors
+    .iter()
+    .map(|(pos, msg)| {
+      Diagnostic::error(msg.clone(), *pos, pos + 1).with_code(ErrorKind::Syntax.code())
+    })
+    .collect()
+}
+
+impl From<RuntimeError> for Error {
+  fn from(e: RuntimeError) -> Error {
+    Error::Runtime(e)
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::Io(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+      Error::Syntax(errors) => match errors.first() {
+        Option::Some((_, msg)) => write!(f, "{}", msg),
+        Option::None => write!(f, "syntax error"),
+      },
+      Error::Runtime(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// runs Plum code for a Rust program
+///
+/// the globals declared by one evaluation are visible to the next ones
+/// ```
+/// # use plum_lang::{Engine, Value};
+/// # fn main() -> Result<(), plum_lang::Error> {
+/// let mut engine = Engine::new();
+/// engine.eval_str("fun add(a, b) { return a + b }")?;
+/// let sum = engine.call("add", &[Value::Int(1), Value::Int(2)])?;
+/// assert_eq!(sum, Value::Int(3));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Engine {
+  interp: Interpreter,
+}
+
+impl Default for Engine {
+  fn default() -> Engine {
+    Engine::new()
+  }
+}
+
+impl Engine {
+  pub fn new() -> Engine {
+    Engine {
+      interp: Interpreter::new(),
+    }
+  }
+
+  /// adds a directory to search for imported modules
+  pub fn add_search_path<P: Into<PathBuf>>(&mut self, dir: P) {
+    self.interp.loader.search_path.push(dir.into());
+  }
+
+  /// sets the bytes of native stack evaluation may use, see
+  /// `Interpreter::max_stack`, for hosts running it on a larger stack
+  pub fn set_max_stack(&mut self, bytes: usize) {
+    self.interp.max_stack = bytes;
+  }
+
+  /// makes a Rust function callable from Plum, see `Interpreter::register`
+  pub fn register<F>(&mut self, name: &str, arity: Arity, f: F)
+  where
+    F: Fn(&mut Interpreter, &Args) -> crate::error::Result<Value> + 'static,
+  {
+    self.interp.register(name, arity, f);
+  }
+
+  /// evaluates source code, returns the value of its last statement
+  ///
+  /// imports are resolved relative to the current directory
+  pub fn eval_str(&mut self, src: &str) -> Result<Value> {
+    let file = self
+      .interp
+      .loader
+      .parse_source(String::from("<string>"), String::from(src))
+      .map_err(Error::Syntax)?;
+    Ok(self.interp.visit_file(&file)?)
+  }
+
+  /// evaluates a file, returns the value of its last statement
+  pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value> {
+    let path = path.as_ref();
+    let src = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let file = self
+      .interp
+      .loader
+      .parse_source(path.display().to_string(), src)
+      .map_err(Error::Syntax)?;
+    Ok(self.interp.run_file(path, &file)?)
+  }
+
+  /// calls the global function `name`
+  pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value> {
+    let f = self.get_global(name).ok_or_else(|| {
+      RuntimeError::unlocated(
+        ErrorKind::UndefinedVariable,
+        format!("undefined function `{}`", name),
+      )
+    })?;
+    Ok(self.interp.call_value(&f, args.to_vec())?)
+  }
+
+  pub fn get_global(&self, name: &str) -> Option<Value> {
+    self.interp.global(name)
+  }
+
+  /// declares a global, or changes the one of that name
+  pub fn set_global(&mut self, name: &str, val: Value) {
+    self.interp.set_global(String::from(name), val);
+  }
+
+  /// renders the diagnostics of an error with the code they point to
+  pub fn render(&self, e: &Error, color: bool) -> String {
+    let renderer = Renderer::new(color);
+    e.diagnostics()
+      .iter()
+      .map(|d| renderer.render(&self.interp.loader.source_map, d))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reports_every_syntax_error_of_a_module() {
+    let dir = std::env::temp_dir().join(format!("plum-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("bad.plum"), "var a = (1\nvar b = )\n").unwrap();
+    let mut engine = Engine::new();
+    engine.add_search_path(&dir);
+    let e = engine.eval_str("import bad").unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+    let diagnostics = e.diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    let rendered = engine.render(&e, false);
+    assert!(rendered.contains("bad.plum:1:"), "{}", rendered);
+    assert!(rendered.contains("bad.plum:2:"), "{}", rendered);
+  }
+
+  #[test]
+  fn deep_recursion_is_an_error() {
+    // on a thread of the default size
+    let t = std::thread::spawn(|| {
+      let mut engine = Engine::new();
+      let src = "fun f(n) { if n == 0 { return 0 }\nreturn f(n - 1) + 1 }
+        fun g(n) {
+          while true { if true { { return map([n], x => g(x + 1))[0] } } }
+        }";
+      engine.eval_str(src).unwrap();
+      for (name, n) in &[("f", 100_000), ("g", 0)] {
+        match engine.call(name, &[Value::Int(*n)]) {
+          Err(Error::Runtime(e)) => assert_eq!(e.kind, ErrorKind::StackOverflow),
+          r => panic!("expected a stack overflow, got {:?}", r),
+        }
+      }
+      // the engine is still usable afterwards
+      let v = engine.call("f", &[Value::Int(50)]).unwrap();
+      assert_eq!(v, Value::Int(50));
+    });
+    t.join().unwrap();
+  }
+}
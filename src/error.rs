@@ -0,0 +1,123 @@
+use crate::ast::Node;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+  /// the program contains a syntax error the parser recovered from
+  Syntax,
+  UndefinedVariable,
+  NotCallable,
+  Arity,
+  Type,
+  DivisionByZero,
+  InvalidLiteral,
+  /// a number does not fit in the type it is converted to
+  Overflow,
+  /// an index is negative or past the end
+  Index,
+  /// a module cannot be found or read, or does not export a name
+  Import,
+  /// a module imports itself, directly or not
+  ImportCycle,
+  /// function calls are nested too deep
+  StackOverflow,
+}
+
+impl ErrorKind {
+  /// stable code shown in diagnostics
+  pub fn code(&self) -> &'static str {
+    match self {
+      ErrorKind::Syntax => "E0001",
+      ErrorKind::UndefinedVariable => "E0100",
+      ErrorKind::NotCallable => "E0101",
+      ErrorKind::Arity => "E0102",
+      ErrorKind::Type => "E0103",
+      ErrorKind::DivisionByZero => "E0104",
+      ErrorKind::InvalidLiteral => "E0105",
+      ErrorKind::Overflow => "E0106",
+      ErrorKind::Index => "E0107",
+      ErrorKind::Import => "E0108",
+      ErrorKind::ImportCycle => "E0109",
+      ErrorKind::StackOverflow => "E0110",
+    }
+  }
+}
+
+/// an error raised while evaluating a program
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+  pub kind: ErrorKind,
+  pub msg: String,
+  /// span of the offending code, `0` if unknown
+  pub pos: usize,
+  pub end: usize,
+  /// every syntax error of an imported module that failed to parse, the
+  /// first one is `msg` at `pos`
+  pub syntax: Vec<(usize, String)>,
+}
+
+pub type Result<T> = std::result::Result<T, RuntimeError>;
+
+impl RuntimeError {
+  pub fn new(kind: ErrorKind, pos: usize, msg: String) -> RuntimeError {
+    RuntimeError {
+      kind,
+      msg,
+      pos,
+      end: pos + 1,
+      syntax: vec![],
+    }
+  }
+
+  /// an error without a position, which the call it is returned to is
+  /// reported at, see `Args`
+  pub fn unlocated(kind: ErrorKind, msg: String) -> RuntimeError {
+    RuntimeError {
+      kind,
+      msg,
+      pos: 0,
+      end: 0,
+      syntax: vec![],
+    }
+  }
+
+  /// places an error that has no position yet at `node`
+  pub fn or_at(self, node: &dyn Node) -> RuntimeError {
+    if self.pos != 0 {
+      return self;
+    }
+    RuntimeError {
+      pos: node.beg(),
+      end: node.end(),
+      ..self
+    }
+  }
+
+  /// an error spanning the whole node
+  pub fn at(kind: ErrorKind, node: &dyn Node, msg: String) -> RuntimeError {
+    RuntimeError {
+      kind,
+      msg,
+      pos: node.beg(),
+      end: node.end(),
+      syntax: vec![],
+    }
+  }
+
+  /// the syntax errors of a module, reported all at once
+  pub fn syntax(errors: Vec<(usize, String)>) -> RuntimeError {
+    let (pos, msg) = errors[0].clone();
+    RuntimeError {
+      syntax: errors,
+      ..RuntimeError::new(ErrorKind::Syntax, pos, msg)
+    }
+  }
+}
+
+impl fmt::Display for RuntimeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.msg)
+  }
+}
+
+impl std::error::Error for RuntimeError {}
@@ -1,171 +1,1114 @@
 use crate::ast::*;
+use crate::bignum::BigInt;
+use crate::builtins;
+use crate::decimal::Decimal;
+use crate::error::*;
+use crate::map::{Key, Map};
+use crate::module::Loader;
+use crate::native::{Args, Arity, Native};
 use crate::token::Token;
+use crate::value::*;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+/// a pending non-local exit, set by `break`, `continue` and `return`
+#[derive(Debug)]
+enum Flow {
+  Break,
+  Continue,
+  Return(Value),
+}
+
+/// default for `Interpreter::max_stack`, half of the 2 MiB a Rust thread gets
+/// by default
+pub const MAX_STACK: usize = 1 << 20;
 
 pub struct Interpreter {
-  stack: Vec<HashMap<String, f64>>,
+  /// innermost scope last
+  stack: Vec<Scope>,
+  /// statements are skipped while a flow is pending
+  flow: Option<Flow>,
+  pub loader: Loader,
+  /// functions implemented in Rust, looked up after every scope
+  natives: HashMap<String, Value>,
+  /// number of statements and expressions being evaluated inside each other
+  depth: usize,
+  /// address on the native stack where the outermost of them started
+  stack_base: usize,
+  /// bytes of native stack evaluation may use, code nested deeper fails with
+  /// a stack overflow error instead of aborting the process
+  pub max_stack: usize,
+}
+
+impl Default for Interpreter {
+  fn default() -> Interpreter {
+    Interpreter::new()
+  }
 }
 
 impl Interpreter {
   pub fn new() -> Interpreter {
-    Interpreter {
-      stack: vec![HashMap::new()],
+    let mut interp = Interpreter {
+      stack: vec![Scope::default()],
+      flow: Option::None,
+      loader: Loader::new(),
+      natives: HashMap::new(),
+      depth: 0,
+      stack_base: 0,
+      max_stack: MAX_STACK,
+    };
+    builtins::register(&mut interp);
+    interp
+  }
+  /// runs the program in `file`, parsed from `path`, which its imports are
+  /// resolved relative to
+  pub fn run_file(&mut self, path: &Path, file: &File) -> Result<Value> {
+    self.loader.enter(path);
+    let ret = self.visit_file(file);
+    self.loader.leave();
+    ret
+  }
+  /// runs the module in `path` in a global scope of its own
+  fn load_module(&mut self, x: &ImportStmt, path: &Path) -> Result<Rc<Module>> {
+    if let Option::Some(cycle) = self.loader.cycle(path) {
+      let names: Vec<String> = cycle.iter().map(|p| p.display().to_string()).collect();
+      return Err(RuntimeError::at(
+        ErrorKind::ImportCycle,
+        x,
+        format!("import cycle: {}", names.join(" -> ")),
+      ));
+    }
+    let file = self
+      .loader
+      .parse(path)
+      .map_err(|mut errors| match errors[0].0 {
+        0 => RuntimeError::at(ErrorKind::Import, &*x.path, errors.remove(0).1),
+        _ => RuntimeError::syntax(errors),
+      })?;
+    let caller = std::mem::replace(&mut self.stack, vec![Scope::default()]);
+    let ret = self.run_file(path, &file);
+    let globals = std::mem::replace(&mut self.stack, caller).remove(0);
+    ret?;
+    let mut exports = HashMap::new();
+    for i in &file.stmts {
+      let name = match &**i {
+        Stmt::Decl(DeclStmt {
+          export_pos: Option::Some(_),
+          decl,
+        }) => match &**decl {
+          Decl::Var(d) => &d.name.name,
+          Decl::Fun(d) => &d.name.name,
+          Decl::Bad(_) => continue,
+        },
+        _ => continue,
+      };
+      if let Option::Some(v) = globals.borrow().get(name) {
+        exports.insert(name.clone(), v.clone());
+      }
     }
+    let name = path
+      .file_stem()
+      .map(|s| s.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    let module = Rc::new(Module { name, exports });
+    self.loader.insert(path, module.clone());
+    Ok(module)
   }
-  fn get(&mut self, key: &String) -> Option<&f64> {
-    let last_idx = self.stack.len() - 1;
-    self.stack[last_idx].get(key)
+  /// looks the scopes up from the innermost outward, then the natives
+  fn get(&self, key: &str) -> Option<Value> {
+    self
+      .stack
+      .iter()
+      .rev()
+      .find_map(|scope| scope.borrow().get(key).cloned())
+      .or_else(|| self.natives.get(key).cloned())
   }
-  fn set(&mut self, key: String, val: f64) -> Option<f64> {
+  /// a variable of the global scope, or a native
+  pub fn global(&self, key: &str) -> Option<Value> {
+    self.stack[0]
+      .borrow()
+      .get(key)
+      .cloned()
+      .or_else(|| self.natives.get(key).cloned())
+  }
+  /// declares `key` in the global scope, or changes it
+  pub fn set_global(&mut self, key: String, val: Value) {
+    self.stack[0].borrow_mut().insert(key, val);
+  }
+  /// declares `key` in the innermost scope, shadowing outer ones
+  fn define(&mut self, key: String, val: Value) {
     let last_idx = self.stack.len() - 1;
-    self.stack[last_idx].insert(key, val)
-  }
-  // fn push(&mut self) {
-  //   self.stack.push(HashMap::new());
-  // }
-  // fn pop(&mut self) {
-  //   self.stack.pop();
-  // }
-  fn call(&mut self, e: &CallExpr) -> f64 {
-    let mut args: Vec<f64> = vec![];
+    self.stack[last_idx].borrow_mut().insert(key, val);
+  }
+  /// updates `key` in the scope declaring it, returns false if there is none
+  fn set(&mut self, key: &str, val: Value) -> bool {
+    for scope in self.stack.iter().rev() {
+      if let Option::Some(v) = scope.borrow_mut().get_mut(key) {
+        *v = val;
+        return true;
+      }
+    }
+    false
+  }
+  /// counts one more statement or expression being evaluated inside the
+  /// others, until the matching `leave`
+  fn enter(&mut self, x: &dyn Node) -> Result<()> {
+    // frames differ in size with the build, measure the stack itself
+    let here = 0u8;
+    let here = &here as *const u8 as usize;
+    if self.depth == 0 {
+      self.stack_base = here;
+    } else if here.abs_diff(self.stack_base) > self.max_stack {
+      return Err(RuntimeError::at(
+        ErrorKind::StackOverflow,
+        x,
+        format!("stack overflow, code is nested {} levels deep", self.depth),
+      ));
+    }
+    self.depth += 1;
+    Ok(())
+  }
+  fn leave(&mut self) {
+    self.depth -= 1;
+  }
+  fn push(&mut self) {
+    self.stack.push(Scope::default());
+  }
+  fn pop(&mut self) {
+    self.stack.pop();
+  }
+  fn call_fun(&mut self, fun: &Function, args: Vec<Value>) -> Result<Value> {
+    let caller = std::mem::replace(&mut self.stack, fun.env.clone());
+    self.push();
+    for (param, val) in fun.params.iter().zip(args) {
+      self.define(param.clone(), val);
+    }
+    let ret = self.visit_block_stmt(&fun.body);
+    self.stack = caller;
+    ret?;
+    match self.flow.take() {
+      Option::Some(Flow::Return(v)) => Ok(v),
+      _ => Ok(Value::Nil),
+    }
+  }
+  fn call(&mut self, e: &CallExpr) -> Result<Value> {
+    let callee = match &*e.fun {
+      Expr::Ident(id) => self.get(&id.name).ok_or_else(|| {
+        RuntimeError::at(
+          ErrorKind::UndefinedVariable,
+          id,
+          format!("undefined function `{}`", id.name),
+        )
+      })?,
+      x => self.visit_expr(x)?,
+    };
+    if !callee.is_callable() {
+      return Err(match &*e.fun {
+        Expr::Ident(id) => RuntimeError::at(
+          ErrorKind::NotCallable,
+          id,
+          format!("`{}` is not a function but {}", id.name, callee.type_name()),
+        ),
+        x => RuntimeError::at(
+          ErrorKind::NotCallable,
+          x,
+          format!("{} is not callable", callee.type_name()),
+        ),
+      });
+    }
+    let mut args: Vec<Value> = vec![];
     for i in &e.args {
-      args.push(self.visit_expr(&*i));
+      args.push(self.visit_expr(i)?);
     }
-    match &*e.fun {
-      Expr::Ident(id) => match id.name.as_str() {
-        "print" => {
-          for i in args {
-            print!("{} ", i);
-          }
-          f64::NAN
-        }
-        "println" => {
-          for i in args {
-            print!("{} ", i);
-          }
-          print!("\n");
-          f64::NAN
-        }
-        _ => f64::NAN,
-      },
-      _ => f64::NAN,
+    self.call_value(&callee, args).map_err(|err| err.or_at(e))
+  }
+  /// calls a function value after checking the number of arguments, errors
+  /// without a position belong to the caller
+  pub fn call_value(&mut self, f: &Value, args: Vec<Value>) -> Result<Value> {
+    match f {
+      Value::Fun(fun) => {
+        Arity::Exact(fun.params.len()).check(&fun.name, args.len())?;
+        self.call_fun(fun, args)
+      }
+      Value::Native(fun) => {
+        fun.arity.check(&fun.name, args.len())?;
+        (fun.fun)(self, &Args::new(&fun.name, args))
+      }
+      v => Err(RuntimeError::unlocated(
+        ErrorKind::NotCallable,
+        format!("{} is not callable", v.type_name()),
+      )),
     }
   }
+  /// makes a Rust function callable by name from every module, variables
+  /// of the same name shadow it
+  ///
+  /// `f` gets the arguments once their number has been checked against `arity`
+  pub fn register<F>(&mut self, name: &str, arity: Arity, f: F)
+  where
+    F: Fn(&mut Interpreter, &Args) -> Result<Value> + 'static,
+  {
+    let native = Native {
+      name: String::from(name),
+      arity,
+      fun: Box::new(f),
+    };
+    self
+      .natives
+      .insert(String::from(name), Value::Native(Rc::new(native)));
+  }
 }
 
-impl Visitor<f64> for Interpreter {
-  fn visit_file(&mut self, file: &File) -> f64 {
-    let mut ret: f64 = 0.0;
+/// the element of a list of length `len` that `x` refers to
+fn list_index(x: &IndexExpr, len: usize, i: &Value) -> Result<usize> {
+  let msg = match i {
+    Value::Int(i) if *i >= 0 && (*i as u64) < len as u64 => return Ok(*i as usize),
+    Value::Int(_) | Value::BigInt(_)
+      if num_cmp(i, &Value::Int(0)) == Option::Some(Ordering::Less) =>
+    {
+      format!("negative list index {}", i)
+    }
+    Value::Int(_) | Value::BigInt(_) => {
+      format!("index {} out of range for a list of length {}", i, len)
+    }
+    v => {
+      return Err(RuntimeError::at(
+        ErrorKind::Type,
+        &*x.index,
+        format!("list index must be an int, not {}", v.type_name()),
+      ))
+    }
+  };
+  Err(RuntimeError::at(ErrorKind::Index, &*x.index, msg))
+}
+
+/// `v` as a map key, `x` is the expression it comes from
+fn map_key(x: &dyn Node, v: &Value) -> Result<Key> {
+  Key::from_value(v).ok_or_else(|| {
+    RuntimeError::at(
+      ErrorKind::Type,
+      x,
+      format!("{} cannot be a map key", v.type_name()),
+    )
+  })
+}
+
+impl Visitor<Result<Value>> for Interpreter {
+  fn visit_file(&mut self, file: &File) -> Result<Value> {
+    let mut ret = Value::Nil;
     for i in &file.stmts {
-      ret = self.visit_stmt(i);
+      ret = self.visit_stmt(i)?;
     }
-    ret
+    Ok(ret)
   }
 
-  fn visit_stmt(&mut self, stmt: &Stmt) -> f64 {
-    match stmt {
+  fn visit_stmt(&mut self, stmt: &Stmt) -> Result<Value> {
+    self.enter(stmt)?;
+    let ret = match stmt {
       Stmt::Bad(x) => self.visit_bad_stmt(x),
       Stmt::Decl(x) => self.visit_decl_stmt(x),
       Stmt::Expr(x) => self.visit_expr_stmt(x),
       Stmt::Assign(x) => self.visit_assign_stmt(x),
-    }
+      Stmt::Import(x) => self.visit_import_stmt(x),
+      Stmt::Return(x) => self.visit_return_stmt(x),
+      Stmt::Block(x) => self.visit_block_stmt(x),
+      Stmt::If(x) => self.visit_if_stmt(x),
+      Stmt::While(x) => self.visit_while_stmt(x),
+      Stmt::Branch(x) => self.visit_branch_stmt(x),
+    };
+    self.leave();
+    ret
   }
-  fn visit_bad_stmt(&mut self, _: &BadStmt) -> f64 {
-    f64::NAN
+  fn visit_bad_stmt(&mut self, x: &BadStmt) -> Result<Value> {
+    Err(RuntimeError::at(
+      ErrorKind::Syntax,
+      x,
+      String::from("bad statement"),
+    ))
   }
-  fn visit_decl_stmt(&mut self, decl_stmt: &DeclStmt) -> f64 {
-    self.visit_decl(&*decl_stmt.decl)
+  fn visit_decl_stmt(&mut self, decl_stmt: &DeclStmt) -> Result<Value> {
+    self.visit_decl(&decl_stmt.decl)
   }
-  fn visit_expr_stmt(&mut self, x: &ExprStmt) -> f64 {
-    self.visit_expr(&*x.x)
+  fn visit_expr_stmt(&mut self, x: &ExprStmt) -> Result<Value> {
+    self.visit_expr(&x.x)
   }
-  fn visit_assign_stmt(&mut self, x: &AssignStmt) -> f64 {
+  fn visit_assign_stmt(&mut self, x: &AssignStmt) -> Result<Value> {
     match &*x.ptr {
       Expr::Ident(id) => {
-        let key = id.name.clone();
-        let val = self.visit_expr(&*x.val);
-        self.set(key, val);
-        val
+        let val = self.visit_expr(&x.val)?;
+        if !self.set(&id.name, val.clone()) {
+          return Err(RuntimeError::at(
+            ErrorKind::UndefinedVariable,
+            id,
+            format!("undefined variable `{}`", id.name),
+          ));
+        }
+        Ok(val)
+      }
+      Expr::Index(ix) => {
+        let xv = self.visit_expr(&ix.x)?;
+        let iv = self.visit_expr(&ix.index)?;
+        let val = self.visit_expr(&x.val)?;
+        match xv {
+          Value::List(l) => {
+            let i = list_index(ix, l.borrow().len(), &iv)?;
+            l.borrow_mut()[i] = val.clone();
+            Ok(val)
+          }
+          Value::Map(m) => {
+            m.borrow_mut()
+              .insert(map_key(&*ix.index, &iv)?, val.clone());
+            Ok(val)
+          }
+          v => Err(RuntimeError::at(
+            ErrorKind::Type,
+            &*ix.x,
+            format!("cannot index {}", v.type_name()),
+          )),
+        }
+      }
+      _ => Err(RuntimeError::at(
+        ErrorKind::Type,
+        &*x.ptr,
+        String::from("cannot assign to expression"),
+      )),
+    }
+  }
+  fn visit_import_stmt(&mut self, x: &ImportStmt) -> Result<Value> {
+    let name = match &*x.path {
+      Expr::BasicLit(BasicLit {
+        tok: Token::String(s),
+        ..
+      }) => s.clone(),
+      Expr::Ident(id) => id.name.clone(),
+      _ => return self.visit_expr(&x.path),
+    };
+    let path = self.loader.resolve(&name).ok_or_else(|| {
+      RuntimeError::at(
+        ErrorKind::Import,
+        &*x.path,
+        format!("cannot find module `{}`", name),
+      )
+    })?;
+    let module = match self.loader.cached(&path) {
+      Option::Some(m) => m,
+      Option::None => self.load_module(x, &path)?,
+    };
+    let alias = match &x.alias {
+      Option::Some(id) => id.name.clone(),
+      Option::None => module.name.clone(),
+    };
+    self.define(alias, Value::Module(module));
+    Ok(Value::Nil)
+  }
+  fn visit_return_stmt(&mut self, x: &ReturnStmt) -> Result<Value> {
+    let val = if let Option::Some(expr) = &x.value {
+      self.visit_expr(expr)?
+    } else {
+      Value::Nil
+    };
+    self.flow = Option::Some(Flow::Return(val.clone()));
+    Ok(val)
+  }
+  fn visit_block_stmt(&mut self, x: &BlockStmt) -> Result<Value> {
+    self.push();
+    let mut ret = Ok(Value::Nil);
+    for i in &x.stmts {
+      ret = self.visit_stmt(i);
+      if ret.is_err() || self.flow.is_some() {
+        break;
+      }
+    }
+    self.pop();
+    ret.map(|_| Value::Nil)
+  }
+  fn visit_if_stmt(&mut self, x: &IfStmt) -> Result<Value> {
+    if self.visit_expr(&x.cond)?.truthy() {
+      self.visit_block_stmt(&x.body)
+    } else if let Option::Some(els) = &x.els {
+      self.visit_stmt(els)
+    } else {
+      Ok(Value::Nil)
+    }
+  }
+  fn visit_while_stmt(&mut self, x: &WhileStmt) -> Result<Value> {
+    while self.visit_expr(&x.cond)?.truthy() {
+      self.visit_block_stmt(&x.body)?;
+      match self.flow {
+        Option::Some(Flow::Break) => {
+          self.flow = Option::None;
+          break;
+        }
+        Option::Some(Flow::Continue) => self.flow = Option::None,
+        Option::Some(Flow::Return(_)) => break,
+        Option::None => (),
       }
-      _ => f64::NAN,
     }
+    Ok(Value::Nil)
+  }
+  fn visit_branch_stmt(&mut self, x: &BranchStmt) -> Result<Value> {
+    self.flow = match x.tok {
+      Token::Break => Option::Some(Flow::Break),
+      Token::Continue => Option::Some(Flow::Continue),
+      _ => Option::None,
+    };
+    Ok(Value::Nil)
   }
 
-  fn visit_decl(&mut self, decl: &Decl) -> f64 {
+  fn visit_decl(&mut self, decl: &Decl) -> Result<Value> {
     match decl {
+      Decl::Bad(bad_decl) => self.visit_bad_decl(bad_decl),
       Decl::Var(var_decl) => self.visit_var_decl(var_decl),
-      _ => panic!(),
+      Decl::Fun(fun_decl) => self.visit_fun_decl(fun_decl),
     }
   }
-  fn visit_bad_decl(&mut self, _: &BadDecl) -> f64 {
-    f64::NAN
+  fn visit_bad_decl(&mut self, x: &BadDecl) -> Result<Value> {
+    Err(RuntimeError::at(
+      ErrorKind::Syntax,
+      x,
+      String::from("bad declaration"),
+    ))
   }
-  fn visit_var_decl(&mut self, decl: &VarDecl) -> f64 {
+  fn visit_var_decl(&mut self, decl: &VarDecl) -> Result<Value> {
     let key = decl.name.name.clone();
     let val = if let Option::Some(expr) = &decl.value {
-      self.visit_expr(&**expr)
+      self.visit_expr(expr)?
     } else {
-      0.0
+      Value::Nil
     };
-    self.set(key, val);
-    val
+    self.define(key, val.clone());
+    Ok(val)
   }
-  fn visit_fun_decl(&mut self, _: &FunDecl) -> f64 {
-    f64::NAN
+  fn visit_fun_decl(&mut self, decl: &FunDecl) -> Result<Value> {
+    let fun = Function {
+      name: decl.name.name.clone(),
+      params: decl.params.iter().map(|p| p.name.clone()).collect(),
+      body: decl.body.clone(),
+      env: self.stack.clone(),
+    };
+    let val = Value::Fun(Rc::new(fun));
+    self.define(decl.name.name.clone(), val.clone());
+    Ok(val)
   }
 
-  fn visit_expr(&mut self, x: &Expr) -> f64 {
-    match x {
-      Expr::Bad(_) => f64::NAN,
+  fn visit_expr(&mut self, x: &Expr) -> Result<Value> {
+    self.enter(x)?;
+    let ret = match x {
+      Expr::Bad(x) => self.visit_bad_expr(x),
       Expr::Ident(x) => self.visit_ident(x),
       Expr::BasicLit(x) => self.visit_basic_lit(x),
+      Expr::Interp(x) => self.visit_interp_expr(x),
+      Expr::List(x) => self.visit_list_lit(x),
+      Expr::Map(x) => self.visit_map_lit(x),
+      Expr::Fun(x) => self.visit_fun_lit(x),
+      Expr::Index(x) => self.visit_index_expr(x),
+      Expr::Selector(x) => self.visit_selector_expr(x),
       Expr::Call(x) => self.visit_call_expr(x),
       Expr::Unary(x) => self.visit_unary_expr(x),
       Expr::Binary(x) => self.visit_binary_expr(x),
       Expr::Paren(x) => self.visit_paren_expr(x),
+    };
+    self.leave();
+    ret
+  }
+  fn visit_bad_expr(&mut self, x: &BadExpr) -> Result<Value> {
+    Err(RuntimeError::at(
+      ErrorKind::Syntax,
+      x,
+      String::from("bad expression"),
+    ))
+  }
+  fn visit_ident(&mut self, x: &Ident) -> Result<Value> {
+    self.get(&x.name).ok_or_else(|| {
+      RuntimeError::at(
+        ErrorKind::UndefinedVariable,
+        x,
+        format!("undefined variable `{}`", x.name),
+      )
+    })
+  }
+  fn visit_basic_lit(&mut self, x: &BasicLit) -> Result<Value> {
+    let val = match &x.tok {
+      Token::Integer(lit) => parse_int(lit).map(Value::from),
+      Token::Float(lit) => lit.replace('_', "").parse::<f64>().ok().map(Value::Float),
+      Token::Decimal(lit) => Decimal::parse(lit).map(Value::from),
+      Token::String(lit) => Option::Some(Value::from(lit.as_str())),
+      Token::True => Option::Some(Value::Bool(true)),
+      Token::False => Option::Some(Value::Bool(false)),
+      Token::Nil => Option::Some(Value::Nil),
+      _ => Option::None,
+    };
+    val.ok_or_else(|| {
+      RuntimeError::at(
+        ErrorKind::InvalidLiteral,
+        x,
+        format!("invalid literal `{}`", x.tok),
+      )
+    })
+  }
+  fn visit_interp_expr(&mut self, x: &InterpExpr) -> Result<Value> {
+    let mut s = String::new();
+    for i in &x.parts {
+      match i {
+        InterpPart::Str(lit) => s.push_str(lit),
+        InterpPart::Expr(x) => s.push_str(&self.visit_expr(x)?.to_string()),
+      }
     }
+    Ok(Value::from(s.as_str()))
   }
-  fn visit_bad_expr(&mut self, _: &BadExpr) -> f64 {
-    f64::NAN
+  fn visit_list_lit(&mut self, x: &ListLit) -> Result<Value> {
+    let mut elts = vec![];
+    for i in &x.elts {
+      elts.push(self.visit_expr(i)?);
+    }
+    Ok(Value::from(elts))
   }
-  fn visit_ident(&mut self, x: &Ident) -> f64 {
-    if let Option::Some(v) = self.get(&x.name) {
-      *v
-    } else {
-      f64::NAN
+  fn visit_map_lit(&mut self, x: &MapLit) -> Result<Value> {
+    let mut m = Map::new();
+    for i in &x.elts {
+      let k = match &*i.key {
+        Expr::Ident(id) => Value::from(id.name.as_str()),
+        k => self.visit_expr(k)?,
+      };
+      let v = self.visit_expr(&i.value)?;
+      m.insert(map_key(&*i.key, &k)?, v);
     }
+    Ok(Value::from(m))
   }
-  fn visit_basic_lit(&mut self, x: &BasicLit) -> f64 {
-    match x.tok.clone() {
-      Token::Integer(lit) => lit.parse::<i64>().unwrap() as f64,
-      Token::Float(lit) => lit.parse::<f64>().unwrap(),
-      _ => f64::NAN,
+  fn visit_fun_lit(&mut self, x: &FunLit) -> Result<Value> {
+    let fun = Function {
+      name: String::from("anonymous"),
+      params: x.params.iter().map(|p| p.name.clone()).collect(),
+      body: x.body.clone(),
+      env: self.stack.clone(),
+    };
+    Ok(Value::Fun(Rc::new(fun)))
+  }
+  fn visit_index_expr(&mut self, x: &IndexExpr) -> Result<Value> {
+    let xv = self.visit_expr(&x.x)?;
+    let iv = self.visit_expr(&x.index)?;
+    match xv {
+      Value::List(l) => {
+        let l = l.borrow();
+        Ok(l[list_index(x, l.len(), &iv)?].clone())
+      }
+      Value::Map(m) => match m.borrow().get(&map_key(&*x.index, &iv)?) {
+        Option::Some(v) => Ok(v.clone()),
+        Option::None => Err(RuntimeError::at(
+          ErrorKind::Index,
+          &*x.index,
+          format!("key {} not found in map", iv.repr()),
+        )),
+      },
+      v => Err(RuntimeError::at(
+        ErrorKind::Type,
+        &*x.x,
+        format!("cannot index {}", v.type_name()),
+      )),
+    }
+  }
+  fn visit_selector_expr(&mut self, x: &SelectorExpr) -> Result<Value> {
+    match self.visit_expr(&x.x)? {
+      Value::Module(m) => m.exports.get(&x.sel.name).cloned().ok_or_else(|| {
+        RuntimeError::at(
+          ErrorKind::Import,
+          &*x.sel,
+          format!("module `{}` does not export `{}`", m.name, x.sel.name),
+        )
+      }),
+      v => Err(RuntimeError::at(
+        ErrorKind::Type,
+        &*x.x,
+        format!("cannot select `{}` from {}", x.sel.name, v.type_name()),
+      )),
     }
   }
-  fn visit_call_expr(&mut self, x: &CallExpr) -> f64 {
+  fn visit_call_expr(&mut self, x: &CallExpr) -> Result<Value> {
     self.call(x)
   }
-  fn visit_unary_expr(&mut self, x: &UnaryExpr) -> f64 {
-    let v = self.visit_expr(&*x.x);
-    match x.op {
-      Token::Add => v,
-      Token::Sub => -v,
-      _ => f64::NAN,
+  fn visit_unary_expr(&mut self, x: &UnaryExpr) -> Result<Value> {
+    let v = self.visit_expr(&x.x)?;
+    match (&x.op, v) {
+      (Token::Exel, v) => Ok(Value::Bool(!v.truthy())),
+      (Token::Add, v) if v.is_number() => Ok(v),
+      (Token::Sub, Value::Int(i)) => Ok(
+        i.checked_neg()
+          .map(Value::Int)
+          .unwrap_or_else(|| Value::from(-&BigInt::from(i))),
+      ),
+      (Token::Sub, Value::BigInt(i)) => Ok(Value::from(-&*i)),
+      (Token::Sub, Value::Float(f)) => Ok(Value::Float(-f)),
+      (Token::Sub, Value::Decimal(d)) => Ok(Value::from(-&*d)),
+      (op, v) => Err(RuntimeError::at(
+        ErrorKind::Type,
+        x,
+        format!("unsupported operand type for {}: {}", op, v.type_name()),
+      )),
     }
   }
-  fn visit_binary_expr(&mut self, x: &BinaryExpr) -> f64 {
-    let xv = self.visit_expr(&*x.x);
-    let yv = self.visit_expr(&*x.y);
-    match x.op {
-      Token::Add => xv + yv,
-      Token::Sub => xv - yv,
-      Token::Mul => xv * yv,
-      Token::Div => xv / yv,
-      Token::Rem => xv % yv,
-      _ => f64::NAN,
+  fn visit_binary_expr(&mut self, x: &BinaryExpr) -> Result<Value> {
+    let xv = self.visit_expr(&x.x)?;
+    // `y` is only evaluated if `x` does not decide the result
+    match (&x.op, xv.truthy()) {
+      (Token::Land, false) => return Ok(Value::Bool(false)),
+      (Token::Lor, true) => return Ok(Value::Bool(true)),
+      (Token::Land, true) | (Token::Lor, false) => {
+        return Ok(Value::Bool(self.visit_expr(&x.y)?.truthy()))
+      }
+      _ => (),
     }
+    let yv = self.visit_expr(&x.y)?;
+    binary_op(x, xv, yv)
+  }
+  fn visit_paren_expr(&mut self, x: &ParenExpr) -> Result<Value> {
+    self.visit_expr(&x.x)
+  }
+}
+
+/// parses an integer literal, with its radix prefix and digit separators
+fn parse_int(lit: &str) -> Option<BigInt> {
+  let lit = lit.replace('_', "");
+  let radix = match lit.get(..2) {
+    Option::Some("0x") | Option::Some("0X") => 16,
+    Option::Some("0o") | Option::Some("0O") => 8,
+    Option::Some("0b") | Option::Some("0B") => 2,
+    _ => return BigInt::parse(&lit, 10),
+  };
+  BigInt::parse(&lit[2..], radix)
+}
+
+/// evaluates a binary operator on two evaluated operands
+///
+/// numbers follow these rules:
+/// - `+`, `-`, `*`, `/` and `%` on two ints give an int, which grows past
+///   64 bits instead of overflowing
+/// - `/` on two ints truncates toward zero and `%` takes the sign of the dividend
+/// - `+%`, `-%` and `*%` on two 64-bit ints wrap around on overflow
+/// - an int and a decimal give a decimal, decimals are exact except for `/`
+///   which keeps `DIV_SCALE` digits after the point
+/// - an int and a float give a float
+/// - a decimal and a float cannot be mixed, except in comparisons
+/// - comparisons between numbers of different types are exact
+fn binary_op(x: &BinaryExpr, xv: Value, yv: Value) -> Result<Value> {
+  let op = &x.op;
+  let val = match (op, &xv, &yv) {
+    (Token::Eql, _, _) => Option::Some(Value::Bool(xv == yv)),
+    (Token::Neq, _, _) => Option::Some(Value::Bool(xv != yv)),
+    (_, Value::Int(a), Value::Int(b)) => int_op(x, *a, *b)?,
+    (_, Value::Str(a), Value::Str(b)) => str_op(op, a, b),
+    _ if xv.is_number() && yv.is_number() => match partial_cmp_op(op, num_cmp(&xv, &yv)) {
+      Option::Some(v) => Option::Some(v),
+      Option::None => num_op(x, &xv, &yv)?,
+    },
+    _ => Option::None,
+  };
+  val.ok_or_else(|| {
+    RuntimeError::at(
+      ErrorKind::Type,
+      x,
+      format!(
+        "unsupported operand types for {}: {} and {}",
+        op,
+        xv.type_name(),
+        yv.type_name()
+      ),
+    )
+  })
+}
+
+/// arithmetic on two numbers that are not both 64-bit ints
+fn num_op(x: &BinaryExpr, xv: &Value, yv: &Value) -> Result<Option<Value>> {
+  match (xv, yv) {
+    (Value::Float(_), Value::Decimal(_)) | (Value::Decimal(_), Value::Float(_)) => Ok(Option::None),
+    (Value::Float(_), _) | (_, Value::Float(_)) => match (xv.to_f64(), yv.to_f64()) {
+      (Option::Some(a), Option::Some(b)) => Ok(float_op(&x.op, a, b)),
+      _ => Ok(Option::None),
+    },
+    (Value::Decimal(_), _) | (_, Value::Decimal(_)) => match (xv.to_decimal(), yv.to_decimal()) {
+      (Option::Some(a), Option::Some(b)) => decimal_op(x, &a, &b),
+      _ => Ok(Option::None),
+    },
+    _ => match (xv.to_big(), yv.to_big()) {
+      (Option::Some(a), Option::Some(b)) => big_op(x, &a, &b),
+      _ => Ok(Option::None),
+    },
+  }
+}
+
+fn int_op(x: &BinaryExpr, a: i64, b: i64) -> Result<Option<Value>> {
+  let val = match x.op {
+    Token::Add => a.checked_add(b),
+    Token::Sub => a.checked_sub(b),
+    Token::Mul => a.checked_mul(b),
+    Token::Div | Token::Rem if b == 0 => return Err(division_by_zero(x)),
+    Token::Div => a.checked_div(b),
+    Token::Rem => a.checked_rem(b),
+    Token::AddWrap => Option::Some(a.wrapping_add(b)),
+    Token::SubWrap => Option::Some(a.wrapping_sub(b)),
+    Token::MulWrap => Option::Some(a.wrapping_mul(b)),
+    _ => return Ok(cmp_op(&x.op, a.cmp(&b))),
+  };
+  match val {
+    Option::Some(v) => Ok(Option::Some(Value::Int(v))),
+    // overflowed, redo it with big ints
+    Option::None => big_op(x, &BigInt::from(a), &BigInt::from(b)),
+  }
+}
+
+fn big_op(x: &BinaryExpr, a: &BigInt, b: &BigInt) -> Result<Option<Value>> {
+  let val = match x.op {
+    Token::Add => a + b,
+    Token::Sub => a - b,
+    Token::Mul => a * b,
+    Token::Div | Token::Rem => match (a.div_rem(b), &x.op) {
+      (Option::Some((q, _)), Token::Div) => q,
+      (Option::Some((_, r)), _) => r,
+      (Option::None, _) => return Err(division_by_zero(x)),
+    },
+    _ => return Ok(Option::None),
+  };
+  Ok(Option::Some(Value::from(val)))
+}
+
+fn decimal_op(x: &BinaryExpr, a: &Decimal, b: &Decimal) -> Result<Option<Value>> {
+  let val = match x.op {
+    Token::Add => a + b,
+    Token::Sub => a - b,
+    Token::Mul => a * b,
+    Token::Div => a.checked_div(b).ok_or_else(|| division_by_zero(x))?,
+    Token::Rem => a.checked_rem(b).ok_or_else(|| division_by_zero(x))?,
+    _ => return Ok(Option::None),
+  };
+  Ok(Option::Some(Value::from(val)))
+}
+
+fn float_op(op: &Token, a: f64, b: f64) -> Option<Value> {
+  match op {
+    Token::Add => Option::Some(Value::Float(a + b)),
+    Token::Sub => Option::Some(Value::Float(a - b)),
+    Token::Mul => Option::Some(Value::Float(a * b)),
+    Token::Div => Option::Some(Value::Float(a / b)),
+    Token::Rem => Option::Some(Value::Float(a % b)),
+    _ => Option::None,
+  }
+}
+
+fn division_by_zero(x: &BinaryExpr) -> RuntimeError {
+  RuntimeError::new(
+    ErrorKind::DivisionByZero,
+    x.op_pos,
+    String::from("division by zero"),
+  )
+}
+
+fn str_op(op: &Token, a: &str, b: &str) -> Option<Value> {
+  match op {
+    Token::Add => Option::Some(Value::from([a, b].concat().as_str())),
+    _ => cmp_op(op, a.cmp(b)),
+  }
+}
+
+fn cmp_op(op: &Token, o: Ordering) -> Option<Value> {
+  match op {
+    Token::Lss => Option::Some(Value::Bool(o.is_lt())),
+    Token::Leq => Option::Some(Value::Bool(o.is_le())),
+    Token::Gtr => Option::Some(Value::Bool(o.is_gt())),
+    Token::Geq => Option::Some(Value::Bool(o.is_ge())),
+    _ => Option::None,
+  }
+}
+
+/// like `cmp_op`, comparisons involving NaN are all false
+fn partial_cmp_op(op: &Token, o: Option<Ordering>) -> Option<Value> {
+  match o {
+    Option::Some(o) => cmp_op(op, o),
+    Option::None => cmp_op(op, Ordering::Equal).map(|_| Value::Bool(false)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lexer::Lexer;
+  use crate::parser::Parser;
+
+  fn eval(src: &str) -> Result<Value> {
+    let mut p = Parser::new(Lexer::new(String::from(src)));
+    let file = p.parse_file();
+    assert!(p.errors.is_empty(), "{:?}", p.errors);
+    Interpreter::new().visit_file(&file)
+  }
+
+  /// the value of `src` and its type
+  fn show(src: &str) -> (String, &'static str) {
+    let v = eval(src).unwrap();
+    (v.to_string(), v.type_name())
+  }
+
+  fn error(src: &str) -> ErrorKind {
+    eval(src).unwrap_err().kind
+  }
+
+  #[test]
+  fn ints_grow_into_big_ints() {
+    assert_eq!(show("1 + 2"), ("3".into(), "int"));
+    assert!(matches!(eval("1 + 2"), Ok(Value::Int(3))));
+    let max_plus_one = eval("9223372036854775807 + 1").unwrap();
+    assert!(matches!(max_plus_one, Value::BigInt(_)));
+    assert_eq!(max_plus_one.to_string(), "9223372036854775808");
+    assert_eq!(show("-9223372036854775808 / -1").0, "9223372036854775808");
+    // and shrink back when they fit again
+    assert!(matches!(
+      eval("2 * 9223372036854775807 / 2"),
+      Ok(Value::Int(i64::MAX))
+    ));
+  }
+
+  #[test]
+  fn mixed_numbers_promote() {
+    assert_eq!(show("1 + 2.0"), ("3.0".into(), "float"));
+    assert_eq!(show("1 + 0.5d"), ("1.5".into(), "decimal"));
+    assert_eq!(show("9223372036854775808 * 1.5d").1, "decimal");
+    assert_eq!(show("9223372036854775808 + 0.5").1, "float");
+    assert_eq!(error("1.5 + 0.5d"), ErrorKind::Type);
+    assert_eq!(error("1 + \"a\""), ErrorKind::Type);
+  }
+
+  #[test]
+  fn mixed_comparisons_are_exact() {
+    assert_eq!(show("1 == 1.0").0, "true");
+    assert_eq!(show("9007199254740993 == 9007199254740992.0").0, "false");
+    assert_eq!(show("9007199254740993 > 9007199254740992.0").0, "true");
+    assert_eq!(show("0.1d + 0.2d == 0.3d").0, "true");
+    assert_eq!(show("0.1 + 0.2 == 0.3").0, "false");
+    assert_eq!(show("0.5d < 1").0, "true");
+  }
+
+  #[test]
+  fn division_truncates_toward_zero() {
+    assert_eq!(show("7 / 2").0, "3");
+    assert_eq!(show("-7 / 2").0, "-3");
+    assert_eq!(show("-7d / 2d").0, "-3.5");
+    assert_eq!(show("1d / 3d").0, "0.33333333333333333333");
+    assert_eq!(show("1.0 / 0").0, "inf");
+    assert_eq!(error("1 / 0"), ErrorKind::DivisionByZero);
+    assert_eq!(error("1 % 0"), ErrorKind::DivisionByZero);
+    assert_eq!(error("1d / 0"), ErrorKind::DivisionByZero);
+  }
+
+  #[test]
+  fn remainder_takes_the_sign_of_the_dividend() {
+    assert_eq!(show("7 % -2").0, "1");
+    assert_eq!(show("-7 % 2").0, "-1");
+    assert_eq!(show("-7.5 % 2").0, "-1.5");
+    assert_eq!(show("-7.5d % 2").0, "-1.5");
+    assert_eq!(show("-9223372036854775809 % 2").0, "-1");
+  }
+
+  #[test]
+  fn wrapping_operators() {
+    assert_eq!(
+      show("9223372036854775807 +% 1"),
+      ("-9223372036854775808".into(), "int")
+    );
+    assert_eq!(show("-9223372036854775808 -% 1").0, "9223372036854775807");
+    assert_eq!(show("4611686018427387904 *% 4").0, "0");
+    assert_eq!(show("2 +% 3").0, "5");
+    assert_eq!(error("1.0 +% 1"), ErrorKind::Type);
+  }
+
+  #[test]
+  fn interpolation() {
+    let src = "var a = 1
+      var m = {k: \"v\"}
+      ";
+    assert_eq!(
+      show(&[src, r#""a=${a}, a+1=${a + 1}""#].concat()).0,
+      "a=1, a+1=2"
+    );
+    assert_eq!(show(&[src, r#""${"<${a}>"}""#].concat()).0, "<1>");
+    assert_eq!(
+      show(&[src, r#""${m["k"]}${[1, "}"]}""#].concat()).0,
+      r#"v[1, "}"]"#
+    );
+    assert_eq!(show(&[src, r#""\${a}""#].concat()).0, "${a}");
+    assert_eq!(show(r#""${1}""#), ("1".into(), "string"));
+    assert_eq!(error(r#""${undefined}""#), ErrorKind::UndefinedVariable);
+  }
+
+  #[test]
+  fn list_builtins() {
+    let src = "var l = [1, 2, 3]
+      push(l, 4)
+      ";
+    assert_eq!(show(&[src, "l"].concat()).0, "[1, 2, 3, 4]");
+    assert_eq!(show(&[src, "pop(l)"].concat()).0, "4");
+    assert_eq!(show(&[src, "pop(l)\nl"].concat()).0, "[1, 2, 3]");
+    assert_eq!(show(&[src, "slice(l, 1, 3)"].concat()).0, "[2, 3]");
+    assert_eq!(show(&[src, "slice(l, 4, 4)"].concat()).0, "[]");
+    assert_eq!(
+      show(&[src, "map(l, x => x * x)"].concat()).0,
+      "[1, 4, 9, 16]"
+    );
+    assert_eq!(
+      show(&[src, "filter(l, x => x % 2 == 0)"].concat()).0,
+      "[2, 4]"
+    );
+    // lists are shared
+    assert_eq!(
+      show(&[src, "var m = l\npush(m, 5)\nlen(l)"].concat()).0,
+      "5"
+    );
+    assert_eq!(error("pop([])"), ErrorKind::Index);
+    assert_eq!(
+      eval("slice([1, 2], 1, 3)").unwrap_err().msg,
+      "slice [1:3] out of range for a list of length 2"
+    );
+    assert_eq!(error("slice([1, 2], 2, 1)"), ErrorKind::Index);
+    assert_eq!(error("push(1, 2)"), ErrorKind::Type);
+    assert_eq!(error("map([1], 1)"), ErrorKind::Type);
   }
-  fn visit_paren_expr(&mut self, x: &ParenExpr) -> f64 {
-    self.visit_expr(&*x.x)
+
+  #[test]
+  fn list_index_errors() {
+    let msg = |src| eval(src).unwrap_err().msg;
+    assert_eq!(show("[1, 2][1]").0, "2");
+    assert_eq!(error("[1, 2][2]"), ErrorKind::Index);
+    assert_eq!(
+      msg("[1, 2][2]"),
+      "index 2 out of range for a list of length 2"
+    );
+    assert_eq!(msg("[1, 2][-1]"), "negative list index -1");
+    assert_eq!(
+      msg("[1, 2][99999999999999999999]"),
+      "index 99999999999999999999 out of range for a list of length 2"
+    );
+    assert_eq!(
+      msg("[1, 2][-99999999999999999999]"),
+      "negative list index -99999999999999999999"
+    );
+    assert_eq!(msg("var l = [1]\nl[-1] = 2"), "negative list index -1");
+    assert_eq!(error("[1, 2][1.0]"), ErrorKind::Type);
+  }
+
+  #[test]
+  fn lists_containing_themselves() {
+    assert_eq!(show("var l = [1]\npush(l, l)\nl").0, "[1, [...]]");
+    let src = "var a = [1]
+      var b = [1]
+      push(a, b)
+      push(b, a)
+      ";
+    assert_eq!(show(&[src, "a"].concat()).0, "[1, [1, [...]]]");
+    assert_eq!(show(&[src, "a == b"].concat()).0, "true");
+    assert_eq!(show(&[src, "push(b, 2)\na == b"].concat()).0, "false");
+  }
+
+  #[test]
+  fn maps_keep_insertion_order() {
+    let src = "var m = {b: 1, a: 2, 3: \"x\"}
+      m[\"c\"] = 3
+      m[\"b\"] = 4
+      delete(m, \"a\")
+      m[\"a\"] = 5
+      ";
+    assert_eq!(
+      show(&[src, "m"].concat()).0,
+      r#"{"b": 4, 3: "x", "c": 3, "a": 5}"#
+    );
+    assert_eq!(show(&[src, "keys(m)"].concat()).0, r#"["b", 3, "c", "a"]"#);
+    assert_eq!(show(&[src, "values(m)"].concat()).0, r#"[4, "x", 3, 5]"#);
+    // order does not matter to equality
+    assert_eq!(show("({a: 1, b: 2} == {b: 2, a: 1})").0, "true");
+  }
+
+  #[test]
+  fn maps_containing_themselves() {
+    let src = "var m = {a: 1}
+      m[\"self\"] = m
+      ";
+    assert_eq!(show(&[src, "m"].concat()).0, r#"{"a": 1, "self": {...}}"#);
+    let src = "var m = {}
+      var n = {}
+      m[\"x\"] = n
+      n[\"x\"] = m
+      var l = [m]
+      m[\"l\"] = l
+      n[\"l\"] = l
+      ";
+    assert_eq!(
+      show(&[src, "l"].concat()).0,
+      r#"[{"x": {"x": {...}, "l": [...]}, "l": [...]}]"#
+    );
+    assert_eq!(show(&[src, "m == n"].concat()).0, "true");
+    assert_eq!(show(&[src, "n[\"y\"] = 1\nm == n"].concat()).0, "false");
+  }
+
+  #[test]
+  fn return_leaves_nested_loops_and_blocks() {
+    let src = "fun find(xs, target) {
+        var i = 0
+        while i < len(xs) {
+          var j = 0
+          while j < len(xs[i]) {
+            if xs[i][j] == target {
+              { return [i, j] }
+            }
+            j = j + 1
+          }
+          i = i + 1
+        }
+        return -1
+      }
+      var grid = [[1, 2], [3, 4]]
+      ";
+    assert_eq!(show(&[src, "find(grid, 3)"].concat()).0, "[1, 0]");
+    assert_eq!(show(&[src, "find(grid, 5)"].concat()).0, "-1");
+  }
+
+  #[test]
+  fn return_without_value_is_nil() {
+    let src = "fun f(x) { if x { return }\nreturn 1 }\n";
+    assert_eq!(show(&[src, "f(true)"].concat()).0, "nil");
+    assert_eq!(show(&[src, "f(false)"].concat()).0, "1");
+    // a loop in the caller keeps running after the callee returned
+    let src = "fun g() { while true { return 1 } }
+      var n = 0
+      while n < 3 { n = n + g() }
+      n";
+    assert_eq!(show(src).0, "3");
+  }
+
+  #[test]
+  fn function_literals_return_a_trailing_expression() {
+    assert_eq!(show("map([1, 2], fun(x) { x * 2 })").0, "[2, 4]");
+    assert_eq!(show("map([1, 2], x => { x + 1 })").0, "[2, 3]");
+    assert_eq!(show("(fun() { var a = 1 })()").0, "nil");
+    assert_eq!(show("(fun(x) { if x { return 1 }\n2 })(true)").0, "1");
+    // declarations keep needing `return`
+    assert_eq!(show("fun f() { 1 }\nf()").0, "nil");
+  }
+
+  #[test]
+  fn closures_share_captured_variables() {
+    let src = "fun counter() {
+        var n = 0
+        return [() => { n = n + 1 }, () => n]
+      }
+      var c = counter()
+      c[0]()
+      c[0]()
+      c[1]()";
+    assert_eq!(show(src).0, "2");
+  }
+
+  #[test]
+  fn default_has_globals_and_builtins() {
+    let mut interp = Interpreter::default();
+    interp.set_global(String::from("x"), Value::Int(1));
+    assert_eq!(interp.global("x"), Option::Some(Value::Int(1)));
+    assert!(interp.global("println").is_some());
   }
 }
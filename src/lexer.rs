@@ -1,24 +1,49 @@
-use crate::token::Token;
+use crate::source::normalize;
+use crate::token::{Segment, Token};
+use unicode_xid::UnicodeXID;
 
 pub struct Lexer {
   chars: Vec<char>,
   ch: char,
   offset: usize,
+  /// added to every position, see `SourceMap::add_file`
+  base: usize,
+  /// line of `ch`, and of the first character of the last token
+  ch_line: usize,
+  tok_line: usize,
   insert_semi: bool,
+  /// whether comments are returned as tokens or skipped
+  comments: bool,
+  pub errors: Vec<(usize, String)>,
 }
 
 impl Lexer {
   pub fn new(src: String) -> Lexer {
+    Lexer::with_base(src, 0)
+  }
+
+  pub fn with_base(src: String, base: usize) -> Lexer {
     let mut ret = Lexer {
-      chars: src.chars().collect(),
+      chars: normalize(&src).chars().collect(),
       offset: 0,
       ch: ' ',
+      base,
+      ch_line: 1,
+      tok_line: 1,
       insert_semi: false,
+      comments: false,
+      errors: Vec::new(),
     };
     ret.next();
     ret
   }
 
+  /// makes the lexer return comments as `Token::Comment`
+  pub fn with_comments(mut self) -> Lexer {
+    self.comments = true;
+    self
+  }
+
   pub fn lex_all(&mut self) -> Vec<(Token, usize)> {
     let mut tokens: Vec<(Token, usize)> = vec![];
     loop {
@@ -31,12 +56,45 @@ impl Lexer {
     tokens
   }
 
+  /// position right after the last lexed token
+  pub fn end(&self) -> usize {
+    self.base + self.offset
+  }
+
+  /// line of the last lexed token
+  pub fn line(&self) -> usize {
+    self.tok_line
+  }
+
   pub fn lex(&mut self) -> (Token, usize) {
+    loop {
+      let (tok, pos) = self.scan();
+      if let (Token::Comment(_), false) = (&tok, self.comments) {
+        continue;
+      }
+      return (tok, self.base + pos);
+    }
+  }
+
+  fn scan(&mut self) -> (Token, usize) {
     self.skip_white_space();
+    self.tok_line = self.ch_line;
     let pos = self.offset;
     let ch = self.ch;
+    if ch == '/' && (self.peek() == '/' || self.peek() == '*') {
+      // a comment running to the end of the line ends the statement like
+      // the newline would, the comment itself is lexed on the next call
+      if self.insert_semi && self.comment_ends_line() {
+        self.insert_semi = false;
+        return (Token::Semicolon, pos);
+      }
+      return (self.lex_comment(), pos);
+    }
     let mut ist = false;
-    let tok = if is_letter(ch) || ch == '_' {
+    let tok = if ch == 'r' && (self.peek() == '"' || self.peek() == '#') {
+      ist = true;
+      self.lex_raw_string()
+    } else if is_ident_start(ch) {
       let (t, i) = lookup(self.lex_ident());
       if i {
         ist = i;
@@ -59,7 +117,10 @@ impl Lexer {
           self.insert_semi = false;
           return (Token::Semicolon, pos);
         }
-        '"' => self.lex_string(),
+        '"' => {
+          ist = true;
+          self.lex_string()
+        }
         '(' => (Token::Lparen, pos),
         ')' => {
           ist = true;
@@ -78,14 +139,42 @@ impl Lexer {
         ',' => (Token::Comma, pos),
         ';' => (Token::Semicolon, pos),
         ':' => (Token::Colon, pos),
-        '!' => (Token::Exel, pos),
-        '=' => (Token::Assign, pos),
-        '+' => (Token::Add, pos),
-        '-' => (Token::Sub, pos),
-        '*' => (Token::Mul, pos),
+        '.' => (Token::Period, pos),
+        '!' => (self.switch2('=', Token::Exel, Token::Neq), pos),
+        '=' if self.ch == '>' => {
+          self.next();
+          (Token::Arrow, pos)
+        }
+        '=' => (self.switch2('=', Token::Assign, Token::Eql), pos),
+        '<' => (self.switch2('=', Token::Lss, Token::Leq), pos),
+        '>' => (self.switch2('=', Token::Gtr, Token::Geq), pos),
+        '|' | '&' if self.ch != ch => {
+          self.error(
+            pos,
+            format!("illegal character `{}`, did you mean `{}{}`?", ch, ch, ch),
+          );
+          ist = self.insert_semi;
+          (Token::Illegal, pos)
+        }
+        '|' => (self.switch2('|', Token::Illegal, Token::Lor), pos),
+        '&' => (self.switch2('&', Token::Illegal, Token::Land), pos),
+        '+' => (self.switch2('%', Token::Add, Token::AddWrap), pos),
+        '-' => (self.switch2('%', Token::Sub, Token::SubWrap), pos),
+        '*' => (self.switch2('%', Token::Mul, Token::MulWrap), pos),
         '/' => (Token::Div, pos),
         '%' => (Token::Rem, pos),
         _ => {
+          // name invisible and look-alike characters by their code point
+          let msg = if ch.is_ascii_graphic() {
+            format!("illegal character `{}`", ch)
+          } else {
+            format!(
+              "illegal character `{}` (U+{:04X})",
+              ch.escape_debug(),
+              ch as u32
+            )
+          };
+          self.error(pos, msg);
           ist = self.insert_semi;
           (Token::Illegal, pos)
         }
@@ -95,54 +184,407 @@ impl Lexer {
     tok
   }
 
+  /// lexes `// ...` up to the end of the line or a nestable `/* ... */`
+  fn lex_comment(&mut self) -> Token {
+    let pos = self.offset;
+    let mut lit = String::from("/");
+    self.next();
+    if self.ch == '/' {
+      while self.ch != '\n' && self.ch != '\0' {
+        lit.push(self.ch);
+        self.next();
+      }
+      return Token::Comment(lit);
+    }
+    lit.push('*');
+    self.next();
+    let mut depth = 1;
+    while depth > 0 {
+      match (self.ch, self.peek()) {
+        ('\0', _) => {
+          self.error(pos, String::from("comment not terminated"));
+          break;
+        }
+        ('/', '*') => depth += 1,
+        ('*', '/') => depth -= 1,
+        (c, _) => {
+          lit.push(c);
+          self.next();
+          continue;
+        }
+      }
+      lit.push(self.ch);
+      self.next();
+      lit.push(self.ch);
+      self.next();
+    }
+    Token::Comment(lit)
+  }
+
+  /// whether the comment starting at `ch` is a line comment or a block
+  /// comment spanning several lines
+  fn comment_ends_line(&self) -> bool {
+    if self.peek() == '/' {
+      return true;
+    }
+    let mut depth = 0;
+    let mut i = self.offset - 1;
+    while i + 1 < self.chars.len() {
+      match (self.chars[i], self.chars[i + 1]) {
+        ('\n', _) => return true,
+        ('/', '*') => {
+          depth += 1;
+          i += 1;
+        }
+        ('*', '/') => {
+          depth -= 1;
+          i += 1;
+          if depth == 0 {
+            return false;
+          }
+        }
+        _ => (),
+      }
+      i += 1;
+    }
+    true
+  }
+
   fn lex_ident(&mut self) -> Token {
     let mut lit = String::from("");
-    while is_letter(self.ch) || is_dec(self.ch) || self.ch == '_' {
+    while is_ident_continue(self.ch) {
       lit.push(self.ch);
       self.next();
     }
     Token::Ident(lit)
   }
 
+  /// lexes decimal, `0x` hexadecimal, `0o` octal and `0b` binary integers, and
+  /// decimal floats with an optional exponent; `_` may separate digits
   fn lex_number(&mut self) -> (Token, usize) {
     let pos = self.offset;
     let mut lit = String::from("");
-    while is_number(self.ch) || self.ch == '_' {
+    let (radix, name) = match (self.ch, self.peek()) {
+      ('0', 'x') | ('0', 'X') => (16, "hexadecimal"),
+      ('0', 'o') | ('0', 'O') => (8, "octal"),
+      ('0', 'b') | ('0', 'B') => (2, "binary"),
+      _ => (10, "decimal"),
+    };
+    let mut float = false;
+    if radix != 10 {
       lit.push(self.ch);
       self.next();
-    }
-    if self.ch == '.' {
       lit.push(self.ch);
       self.next();
-      while is_number(self.ch) || self.ch == '_' {
+      let digits = self.lex_digits(&mut lit, radix);
+      if !digits.chars().any(|c| c != '_') {
+        self.error(pos, format!("{} literal has no digits", name));
+      } else if let Option::Some(c) = digits
+        .chars()
+        .find(|&c| c != '_' && c.to_digit(radix).is_none())
+      {
+        self.error(pos, format!("invalid digit `{}` in {} literal", c, name));
+      }
+    } else {
+      self.lex_digits(&mut lit, 10);
+      if self.ch == '.' {
+        float = true;
+        lit.push(self.ch);
+        self.next();
+        self.lex_digits(&mut lit, 10);
+      }
+      if self.ch == 'e' || self.ch == 'E' {
+        float = true;
         lit.push(self.ch);
         self.next();
+        if self.ch == '+' || self.ch == '-' {
+          lit.push(self.ch);
+          self.next();
+        }
+        if !is_dec(self.lex_digits(&mut lit, 10).chars().next().unwrap_or(' ')) {
+          self.error(pos, String::from("exponent has no digits"));
+        }
       }
+    }
+    if !valid_separators(&lit, radix) {
+      self.error(pos, String::from("`_` must separate successive digits"));
+    }
+    // `d` makes a decimal literal out of a decimal integer or float
+    if radix == 10 && self.ch == 'd' && !is_ident_continue(self.peek()) {
+      self.next();
+      self.lex_number_suffix(pos);
+      return (Token::Decimal(lit), pos);
+    }
+    self.lex_number_suffix(pos);
+    if float {
       (Token::Float(lit), pos)
     } else {
       (Token::Integer(lit), pos)
     }
   }
 
+  /// appends the digits of the given radix and `_` to `lit`, returns them
+  fn lex_digits(&mut self, lit: &mut String, radix: u32) -> String {
+    let mut digits = String::from("");
+    // binary and octal literals take any decimal digit so that invalid
+    // ones are reported instead of starting a new token
+    while (radix == 16 && is_hex(self.ch)) || (radix != 16 && is_dec(self.ch)) || self.ch == '_' {
+      digits.push(self.ch);
+      self.next();
+    }
+    lit.push_str(&digits);
+    digits
+  }
+
+  /// reports anything glued to the end of a number literal, like `1.2.3` or `12px`
+  fn lex_number_suffix(&mut self, pos: usize) {
+    let mut rest = String::from("");
+    while is_ident_continue(self.ch) || (self.ch == '.' && is_dec(self.peek())) {
+      rest.push(self.ch);
+      self.next();
+    }
+    if rest.starts_with('.') {
+      self.error(
+        pos,
+        String::from("number literal has more than one decimal point"),
+      );
+    } else if !rest.is_empty() {
+      self.error(pos, format!("invalid suffix `{}` on number literal", rest));
+    }
+  }
+
+  /// lexes `"..."` or the multi-line `"""..."""`, the opening quote has been eaten
   fn lex_string(&mut self) -> (Token, usize) {
     let pos = self.offset - 1;
+    let triple = self.ch == '"' && self.peek() == '"';
+    if triple {
+      self.next();
+      self.next();
+      // a newline right after the opening quotes is not part of the string
+      if self.ch == '\n' {
+        self.next();
+      }
+    }
     let mut lit = String::from("");
+    let mut segs: Vec<Segment> = vec![];
     loop {
-      let c = self.ch;
-      if c == '\n' || c == '\0' {
-        break;
+      match self.ch {
+        '\0' => {
+          self.error(pos, String::from("string literal not terminated"));
+          break;
+        }
+        '$' if self.peek() == '{' => {
+          if !lit.is_empty() {
+            segs.push(Segment::Str(std::mem::take(&mut lit)));
+          }
+          if !self.lex_interp(&mut segs) {
+            break;
+          }
+        }
+        '\n' if !triple => {
+          self.error(pos, String::from("string literal not terminated"));
+          break;
+        }
+        '"' if !triple => {
+          self.next();
+          break;
+        }
+        '"' if self.peek() == '"' && self.chars.get(self.offset + 1) == Option::Some(&'"') => {
+          self.next();
+          self.next();
+          self.next();
+          break;
+        }
+        '\\' => {
+          self.next();
+          if let Option::Some(c) = self.lex_escape() {
+            lit.push(c);
+          }
+        }
+        c => {
+          lit.push(c);
+          self.next();
+        }
+      }
+    }
+    if segs.is_empty() {
+      return (Token::String(lit), pos);
+    }
+    if !lit.is_empty() {
+      segs.push(Segment::Str(lit));
+    }
+    (Token::Interp(segs), pos)
+  }
+
+  /// lexes `${...}` inside a string, returns false if it is not terminated
+  fn lex_interp(&mut self, segs: &mut Vec<Segment>) -> bool {
+    let pos = self.offset;
+    // `ch` is '$', the code starts after the '{'
+    let beg = self.offset + 1;
+    let end = match self.code_end(beg) {
+      Option::Some(end) => end,
+      Option::None => {
+        self.error(pos, String::from("interpolation not terminated"));
+        // the rest of the line belongs to the string
+        while self.ch != '\n' && self.ch != '\0' {
+          self.next();
+        }
+        return false;
+      }
+    };
+    let code: String = self.chars[beg..end].iter().collect();
+    if code.trim().is_empty() {
+      self.error(pos, String::from("empty interpolation"));
+    } else {
+      segs.push(Segment::Code(code, self.base + beg + 1));
+    }
+    // move to the character after the '}'
+    while self.offset <= end + 1 {
+      self.next();
+    }
+    true
+  }
+
+  /// index of the '}' closing the code of an interpolation starting at index `i`
+  fn code_end(&self, mut i: usize) -> Option<usize> {
+    let mut depth = 0;
+    while let Option::Some(&c) = self.chars.get(i) {
+      match c {
+        '{' => depth += 1,
+        '}' if depth == 0 => return Option::Some(i),
+        '}' => depth -= 1,
+        '"' => {
+          i = self.str_end(i + 1)?;
+          continue;
+        }
+        _ => (),
+      }
+      i += 1;
+    }
+    Option::None
+  }
+
+  /// index after the '"' closing a string nested in an interpolation, starting at index `i`
+  fn str_end(&self, mut i: usize) -> Option<usize> {
+    while let Option::Some(&c) = self.chars.get(i) {
+      match c {
+        '"' => return Option::Some(i + 1),
+        '\n' => return Option::None,
+        '\\' => i += 1,
+        '$' if self.chars.get(i + 1) == Option::Some(&'{') => i = self.code_end(i + 2)?,
+        _ => (),
       }
+      i += 1;
+    }
+    Option::None
+  }
+
+  /// lexes the escape sequence after a backslash
+  fn lex_escape(&mut self) -> Option<char> {
+    let pos = self.offset - 1;
+    let c = self.ch;
+    self.next();
+    let ret = match c {
+      'n' => '\n',
+      't' => '\t',
+      'r' => '\r',
+      '0' => '\0',
+      '\\' | '"' | '\'' | '$' => c,
+      'u' => return self.lex_unicode_escape(pos),
+      _ => {
+        self.error(pos, format!("unknown escape sequence `\\{}`", c));
+        return Option::None;
+      }
+    };
+    Option::Some(ret)
+  }
+
+  /// lexes `{XXXX}` after `\u`, with one to six hex digits
+  fn lex_unicode_escape(&mut self, pos: usize) -> Option<char> {
+    if self.ch != '{' {
+      self.error(pos, String::from("expected `{` after `\\u`"));
+      return Option::None;
+    }
+    self.next();
+    let mut digits = String::from("");
+    while is_hex(self.ch) {
+      digits.push(self.ch);
       self.next();
-      if c == '"' {
-        break;
+    }
+    if self.ch != '}' || digits.is_empty() || digits.len() > 6 {
+      self.error(pos, String::from("malformed unicode escape"));
+      return Option::None;
+    }
+    self.next();
+    let c = u32::from_str_radix(&digits, 16)
+      .ok()
+      .and_then(char::from_u32);
+    if c.is_none() {
+      self.error(
+        pos,
+        format!("invalid unicode character `\\u{{{}}}`", digits),
+      );
+    }
+    c
+  }
+
+  /// lexes `r"..."` or `r#"..."#` with any number of `#`, without escapes
+  fn lex_raw_string(&mut self) -> (Token, usize) {
+    let pos = self.offset;
+    self.next(); // eat 'r'
+    let mut hashes = 0;
+    while self.ch == '#' {
+      hashes += 1;
+      self.next();
+    }
+    if self.ch != '"' {
+      self.error(pos, String::from("expected `\"` to start the raw string"));
+      return (Token::Illegal, pos);
+    }
+    self.next();
+    let mut lit = String::from("");
+    loop {
+      match self.ch {
+        '\0' => {
+          self.error(pos, String::from("raw string literal not terminated"));
+          break;
+        }
+        '"' if (0..hashes).all(|i| self.chars.get(self.offset + i) == Option::Some(&'#')) => {
+          for _ in 0..=hashes {
+            self.next();
+          }
+          break;
+        }
+        c => {
+          lit.push(c);
+          self.next();
+        }
       }
-      lit.push(c);
     }
     (Token::String(lit), pos)
   }
 }
 
 impl Lexer {
+  fn error(&mut self, pos: usize, msg: String) {
+    self.errors.push((self.base + pos, msg))
+  }
+
+  /// the character after `ch`
+  fn peek(&self) -> char {
+    self.chars.get(self.offset).copied().unwrap_or('\0')
+  }
+
+  /// returns `tok1` and eats `ch` if it is the next character, `tok0` otherwise
+  fn switch2(&mut self, ch: char, tok0: Token, tok1: Token) -> Token {
+    if self.ch == ch {
+      self.next();
+      tok1
+    } else {
+      tok0
+    }
+  }
+
   fn skip_white_space(&mut self) {
     while self.ch == ' '
       || self.ch == '\t'
@@ -154,18 +596,27 @@ impl Lexer {
   }
 
   fn next(&mut self) {
+    if self.ch == '\n' {
+      self.ch_line += 1;
+    }
     if self.offset < self.chars.len() {
       self.ch = self.chars[self.offset];
       self.offset += 1;
     } else {
+      // EOF is positioned right after the last character
       self.ch = '\0';
-      self.offset = self.chars.len();
+      self.offset = self.chars.len() + 1;
     }
   }
 }
 
-fn is_letter(ch: char) -> bool {
-  (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z')
+/// identifiers follow Unicode's XID_Start and XID_Continue, plus a leading `_`
+fn is_ident_start(ch: char) -> bool {
+  ch == '_' || ch.is_xid_start()
+}
+
+fn is_ident_continue(ch: char) -> bool {
+  ch.is_xid_continue()
 }
 
 fn is_number(ch: char) -> bool {
@@ -173,12 +624,19 @@ fn is_number(ch: char) -> bool {
 }
 
 fn is_dec(ch: char) -> bool {
-  ch >= '0' && ch <= '9'
+  ch.is_ascii_digit()
 }
 
-// fn is_hex(ch: char) -> bool {
-//   (ch >= '0' && ch <= '9') || (ch >= 'a' && ch <= 'f') || (ch >= 'A' && ch <= 'F')
-// }
+fn is_hex(ch: char) -> bool {
+  ch.is_ascii_hexdigit()
+}
+
+/// whether every `_` in a number literal sits between two digits
+fn valid_separators(lit: &str, radix: u32) -> bool {
+  let chars: Vec<char> = lit.chars().collect();
+  let is_digit = |i: usize| chars.get(i).is_some_and(|&c| c.is_digit(radix.max(10)));
+  (0..chars.len()).all(|i| chars[i] != '_' || (i > 0 && is_digit(i - 1) && is_digit(i + 1)))
+}
 
 fn lookup(tok: Token) -> (Token, bool) {
   if let Token::Ident(lit) = tok {
@@ -186,10 +644,301 @@ fn lookup(tok: Token) -> (Token, bool) {
       "fun" => (Token::Fun, false),
       "var" => (Token::Var, false),
       "import" => (Token::Import, false),
+      "export" => (Token::Export, false),
+      "as" => (Token::As, false),
       "return" => (Token::Return, true),
+      "if" => (Token::If, false),
+      "else" => (Token::Else, false),
+      "while" => (Token::While, false),
+      "break" => (Token::Break, true),
+      "continue" => (Token::Continue, true),
+      "true" => (Token::True, true),
+      "false" => (Token::False, true),
+      "nil" => (Token::Nil, true),
       _ => (Token::Ident(lit), true),
     }
   } else {
     (tok, false)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type Errors = Vec<(usize, String)>;
+
+  /// the tokens of `src` with their positions, and the errors
+  fn lex(src: &str) -> (Vec<(Token, usize)>, Errors) {
+    let mut lexer = Lexer::new(String::from(src));
+    let tokens = lexer.lex_all();
+    (tokens, lexer.errors)
+  }
+
+  /// the tokens of `src` as they are printed by `--tok`, which has no errors
+  fn tokens(src: &str) -> Vec<String> {
+    let (tokens, errors) = lex(src);
+    assert!(errors.is_empty(), "{:?}", errors);
+    tokens.iter().map(|(t, _)| format!("{:?}", t)).collect()
+  }
+
+  /// the value of the string literal `src`
+  fn string(src: &str) -> String {
+    match lex(src) {
+      (tokens, errors) if errors.is_empty() => match &tokens[0].0 {
+        Token::String(s) => s.clone(),
+        t => panic!("{:?} is not a string", t),
+      },
+      (_, errors) => panic!("{:?}", errors),
+    }
+  }
+
+  /// the errors of `src`, positions start at 1
+  fn errors(src: &str) -> Errors {
+    lex(src).1
+  }
+
+  /// the position of the identifier `name` in `src`
+  fn ident_pos(src: &str, name: &str) -> Option<usize> {
+    let tokens = lex(src).0;
+    tokens.into_iter().find_map(|t| match t {
+      (Token::Ident(s), pos) if s == name => Option::Some(pos),
+      _ => Option::None,
+    })
+  }
+
+  fn error(pos: usize, msg: &str) -> Errors {
+    vec![(pos, String::from(msg))]
+  }
+
+  #[test]
+  fn string_escapes() {
+    assert_eq!(string(r#""a\nb\tc\r\0""#), "a\nb\tc\r\0");
+    assert_eq!(string(r#""\\ \" \' \$""#), "\\ \" ' $");
+    assert_eq!(string(r#""\u{41}\u{e9}\u{1F600}""#), "Aé😀");
+    assert_eq!(
+      errors(r#""a\qb""#),
+      error(3, "unknown escape sequence `\\q`")
+    );
+    assert_eq!(
+      errors(r#""\u{110000}""#),
+      error(2, "invalid unicode character `\\u{110000}`")
+    );
+    assert_eq!(
+      errors(r#""\u{D800}""#),
+      error(2, "invalid unicode character `\\u{D800}`")
+    );
+    assert_eq!(errors(r#""\u{}""#), error(2, "malformed unicode escape"));
+    assert_eq!(
+      errors(r#""\u{1234567}""#),
+      error(2, "malformed unicode escape")
+    );
+    assert_eq!(errors(r#""\u0041""#), error(2, "expected `{` after `\\u`"));
+  }
+
+  #[test]
+  fn unterminated_strings() {
+    let msg = "string literal not terminated";
+    assert_eq!(errors("\"abc"), error(1, msg));
+    assert_eq!(errors("x = \"abc\ny"), error(5, msg));
+    // the next line is lexed normally
+    assert_eq!(ident_pos("\"abc\ny", "y"), Option::Some(6));
+    assert_eq!(errors("\"\"\"abc\n\""), error(1, msg));
+    assert_eq!(
+      errors("r#\"abc\"\n"),
+      error(1, "raw string literal not terminated")
+    );
+    assert_eq!(
+      errors("r#abc"),
+      error(1, "expected `\"` to start the raw string")
+    );
+  }
+
+  #[test]
+  fn raw_strings() {
+    assert_eq!(string(r#"r"a\nb""#), "a\\nb");
+    assert_eq!(string(r##"r#"a"b"#"##), "a\"b");
+    assert_eq!(string(r###"r##"a"#b"##"###), "a\"#b");
+    assert_eq!(string("r\"a\nb\""), "a\nb");
+    assert_eq!(string(r#"r"${a}""#), "${a}");
+    // `r` alone is still an identifier
+    assert_eq!(
+      tokens("r + 1"),
+      ["Ident(\"r\")", "Add", "Integer(\"1\")", "Semicolon"]
+    );
+  }
+
+  #[test]
+  fn triple_quoted_strings() {
+    // the newline after the opening quotes is left out
+    assert_eq!(
+      string("\"\"\"\nline 1\n  \"line\" 2\n\"\"\""),
+      "line 1\n  \"line\" 2\n"
+    );
+    assert_eq!(string("\"\"\"a \\t \"\" b\"\"\""), "a \t \"\" b");
+    assert_eq!(string("\"\"\"\"\"\""), "");
+    // an empty string followed by something else
+    assert_eq!(
+      tokens("\"\" x"),
+      ["String(\"\")", "Ident(\"x\")", "Semicolon"]
+    );
+  }
+
+  #[test]
+  fn number_literals() {
+    let cases = [
+      ("1_000", "Integer(\"1_000\")"),
+      ("0xf_f", "Integer(\"0xf_f\")"),
+      ("0XFF", "Integer(\"0XFF\")"),
+      ("0o17", "Integer(\"0o17\")"),
+      ("0b1010_0101", "Integer(\"0b1010_0101\")"),
+      ("1.5", "Float(\"1.5\")"),
+      ("1e-9", "Float(\"1e-9\")"),
+      ("2.5E+10", "Float(\"2.5E+10\")"),
+      ("1_000.000_1", "Float(\"1_000.000_1\")"),
+      ("1.5d", "Decimal(\"1.5\")"),
+      ("1e3d", "Decimal(\"1e3\")"),
+    ];
+    for (src, tok) in &cases {
+      assert_eq!(tokens(src), [*tok, "Semicolon"], "{}", src);
+    }
+    // a method-like suffix is not part of the number
+    assert_eq!(
+      tokens("1 dx"),
+      ["Integer(\"1\")", "Ident(\"dx\")", "Semicolon"]
+    );
+  }
+
+  #[test]
+  fn malformed_number_literals() {
+    let separators = "`_` must separate successive digits";
+    let cases = [
+      ("1.2.3", "number literal has more than one decimal point"),
+      ("0x", "hexadecimal literal has no digits"),
+      ("0o", "octal literal has no digits"),
+      ("1e", "exponent has no digits"),
+      ("1e+", "exponent has no digits"),
+      ("0b102", "invalid digit `2` in binary literal"),
+      ("0o78", "invalid digit `8` in octal literal"),
+      ("1__0", separators),
+      ("1_", separators),
+      ("0x_ff", separators),
+      ("1_.5", separators),
+      ("1._5", separators),
+      ("12px", "invalid suffix `px` on number literal"),
+      ("0xffg", "invalid suffix `g` on number literal"),
+      ("1.5dd", "invalid suffix `dd` on number literal"),
+    ];
+    for (src, msg) in &cases {
+      assert_eq!(errors(src), error(1, msg), "{}", src);
+    }
+    // reported where the literal starts, as one token
+    let (tokens, errors) = lex("x = 1.2.3");
+    assert_eq!(
+      errors,
+      error(5, "number literal has more than one decimal point")
+    );
+    assert_eq!(tokens.len(), 4);
+  }
+
+  #[test]
+  fn interpolation_segments() {
+    assert_eq!(
+      tokens(r#""a${x + 1}b""#),
+      [
+        r#"Interp([Str("a"), Code("x + 1", 5), Str("b")])"#,
+        "Semicolon"
+      ]
+    );
+    // strings nested in the code, with braces and interpolations of their own
+    assert_eq!(
+      tokens(r#""${"a${1}b"}""#),
+      [r#"Interp([Code("\"a${1}b\"", 4)])"#, "Semicolon"]
+    );
+    assert_eq!(
+      tokens(r#""${f("}")}!""#),
+      [r#"Interp([Code("f(\"}\")", 4), Str("!")])"#, "Semicolon"]
+    );
+    assert_eq!(
+      tokens(r#""${ {a: 1}["a"] }""#),
+      [r#"Interp([Code(" {a: 1}[\"a\"] ", 4)])"#, "Semicolon"]
+    );
+    // escaped and lone dollars are text
+    assert_eq!(
+      tokens(r#""\${a} $a $""#),
+      [r#"String("${a} $a $")"#, "Semicolon"]
+    );
+    assert_eq!(tokens(r#"r"${a}""#), [r#"String("${a}")"#, "Semicolon"]);
+  }
+
+  #[test]
+  fn malformed_interpolations() {
+    assert_eq!(errors(r#""a${}b""#), error(3, "empty interpolation"));
+    assert_eq!(errors(r#""a${ }b""#), error(3, "empty interpolation"));
+    let msg = "interpolation not terminated";
+    assert_eq!(errors(r#""a${x"#), error(3, msg));
+    assert_eq!(errors(r#""a${x""#), error(3, msg));
+    assert_eq!(errors("\"a${\"x}\nb"), error(3, msg));
+    // the next line is lexed normally
+    assert_eq!(ident_pos("\"a${x\ny", "y"), Option::Some(7));
+  }
+
+  /// the tokens of `src` when comments are kept
+  fn tokens_with_comments(src: &str) -> Vec<String> {
+    let mut lexer = Lexer::new(String::from(src)).with_comments();
+    let tokens = lexer.lex_all();
+    assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+    tokens.iter().map(|(t, _)| format!("{:?}", t)).collect()
+  }
+
+  #[test]
+  fn semicolons_around_comments() {
+    let (x, y, semi) = ("Ident(\"x\")", "Ident(\"y\")", "Semicolon");
+    let comment = |c: &'static str| format!("Comment({:?})", c);
+    // a line comment ends the line before it is lexed
+    let src = "x // c\ny";
+    assert_eq!(tokens(src), [x, semi, y, semi]);
+    assert_eq!(
+      tokens_with_comments(src),
+      [x, semi, &comment("// c"), y, semi]
+    );
+    // a block comment on one line is like a space
+    let src = "x /* one line */\ny";
+    assert_eq!(tokens(src), [x, semi, y, semi]);
+    assert_eq!(
+      tokens_with_comments(src),
+      [x, &comment("/* one line */"), semi, y, semi]
+    );
+    let src = "x /* a */ y";
+    assert_eq!(tokens(src), [x, y, semi]);
+    // and one spanning lines like a newline
+    let src = "x /* multi\nline */ y";
+    assert_eq!(tokens(src), [x, semi, y, semi]);
+    assert_eq!(
+      tokens_with_comments(src),
+      [x, semi, &comment("/* multi\nline */"), y, semi]
+    );
+    // nested comments end at the matching `*/`
+    let src = "x /* /* */ */ y";
+    assert_eq!(tokens(src), [x, y, semi]);
+    assert_eq!(
+      tokens_with_comments(src),
+      [x, &comment("/* /* */ */"), y, semi]
+    );
+    let src = "x /* /* */\n */ y";
+    assert_eq!(tokens(src), [x, semi, y, semi]);
+    // nothing to end after an operator
+    let src = "x + // c\ny";
+    assert_eq!(tokens(src), [x, "Add", y, semi]);
+    assert_eq!(tokens("x // c"), [x, semi]);
+  }
+
+  #[test]
+  fn unterminated_comments() {
+    assert_eq!(errors("x /* a /* b */"), error(3, "comment not terminated"));
+    assert_eq!(
+      tokens("/* a */ / 2"),
+      ["Div", "Integer(\"2\")", "Semicolon"]
+    );
+  }
+}
@@ -0,0 +1,21 @@
+pub mod ast;
+pub mod bignum;
+pub mod builtins;
+pub mod decimal;
+pub mod diagnostic;
+pub mod engine;
+pub mod error;
+pub mod interpreter;
+pub mod lexer;
+pub mod map;
+pub mod module;
+pub mod native;
+pub mod parser;
+pub mod source;
+pub mod token;
+pub mod value;
+
+pub use engine::{Engine, Error};
+pub use error::{ErrorKind, RuntimeError};
+pub use native::{Args, Arity};
+pub use value::Value;
@@ -1,15 +1,16 @@
-pub mod ast;
-pub mod interpreter;
-pub mod lexer;
-pub mod parser;
-pub mod token;
-
+use std::env;
 use std::fs;
+use std::io::{self, IsTerminal};
+use std::process;
+use std::thread;
 
-use ast::*;
-use interpreter::Interpreter;
-use lexer::Lexer;
-use parser::Parser;
+use plum_lang::ast::*;
+use plum_lang::diagnostic::{Diagnostic, Renderer};
+use plum_lang::error::ErrorKind;
+use plum_lang::lexer::Lexer;
+use plum_lang::parser::Parser;
+use plum_lang::source::SourceMap;
+use plum_lang::Engine;
 
 use clap::Parser as AParser;
 
@@ -23,8 +24,19 @@ struct Command {
 
   #[clap(long)]
   tok: bool,
+
+  /// directory to search for imported modules, before those in PLUM_PATH
+  #[clap(long = "path", multiple_occurrences = true, number_of_values = 1)]
+  path: Vec<String>,
+
+  /// whether to color diagnostics
+  #[clap(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+  color: String,
 }
 
+/// native stack of the thread running the program
+const STACK_SIZE: usize = 64 << 20;
+
 fn main() {
   let c: Command = Command::parse();
   if c.ast {
@@ -33,52 +45,87 @@ fn main() {
   if c.tok {
     return print_tokens(c);
   }
-  interpret(c);
+  // deep recursion in Plum is deep recursion in Rust
+  let t = thread::Builder::new()
+    .stack_size(STACK_SIZE)
+    .spawn(move || interpret(c))
+    .expect("cannot start the interpreter thread");
+  if t.join().is_err() {
+    process::exit(101);
+  }
 }
 
 fn interpret(c: Command) {
-  let lexer = if let Ok(s) = fs::read_to_string(c.input) {
-    Lexer::new(s)
-  } else {
-    panic!("file error");
-  };
-  let mut parser = Parser::new(lexer);
-  let ast = parser.parse_file();
-  if parser.errors.len() != 0 {
-    for i in parser.errors {
-      println!("{} {:?}", i.0, i.1);
+  let mut engine = Engine::new();
+  engine.set_max_stack(STACK_SIZE / 2);
+  for dir in &c.path {
+    engine.add_search_path(dir);
+  }
+  if let Option::Some(paths) = env::var_os("PLUM_PATH") {
+    for dir in env::split_paths(&paths) {
+      engine.add_search_path(dir);
     }
-    panic!("parser errors")
   }
-  let mut interpreter = Interpreter::new();
-  interpreter.visit_file(&*ast);
+  if let Err(e) = engine.eval_file(&c.input) {
+    eprint!("{}", engine.render(&e, color(&c)));
+    process::exit(1);
+  }
 }
 
 fn print_ast(c: Command) {
-  let lexer = if let Ok(s) = fs::read_to_string(c.input) {
-    Lexer::new(s)
-  } else {
-    panic!("file error");
+  let mut source_map = SourceMap::new();
+  let ast = parse(&c, &mut source_map);
+  ast.print(0);
+}
+
+fn print_tokens(c: Command) {
+  let mut source_map = SourceMap::new();
+  let mut lexer = load(&c, &mut source_map).with_comments();
+  let tokens = lexer.lex_all();
+  for (tok, pos) in tokens {
+    println!("{} {:?}", source_map.position(pos), tok);
+  }
+  for (pos, msg) in &lexer.errors {
+    let d = Diagnostic::error(msg.clone(), *pos, pos + 1).with_code(ErrorKind::Syntax.code());
+    report(&c, &source_map, &d);
+  }
+}
+
+/// reads the input file and registers it in the source map
+fn load(c: &Command, source_map: &mut SourceMap) -> Lexer {
+  let src = match fs::read_to_string(&c.input) {
+    Ok(s) => s,
+    Err(e) => {
+      eprintln!("error: cannot read {}: {}", c.input, e);
+      process::exit(1);
+    }
   };
-  let mut parser = Parser::new(lexer);
+  let base = source_map.add_file(c.input.clone(), src.clone());
+  Lexer::with_base(src, base)
+}
+
+/// parses the input file, exits after reporting the syntax errors if there are any
+fn parse(c: &Command, source_map: &mut SourceMap) -> Box<File> {
+  let mut parser = Parser::new(load(c, source_map));
   let ast = parser.parse_file();
-  if parser.errors.len() != 0 {
-    for i in parser.errors {
-      println!("{} {:?}", i.0, i.1);
+  if !parser.errors.is_empty() {
+    for (pos, msg) in &parser.errors {
+      let d = Diagnostic::error(msg.clone(), *pos, pos + 1).with_code(ErrorKind::Syntax.code());
+      report(c, source_map, &d);
     }
-    panic!("parser errors")
+    process::exit(1);
   }
-  ast.print(0);
+  ast
 }
 
-fn print_tokens(c: Command) {
-  let mut lexer = if let Ok(s) = fs::read_to_string(c.input) {
-    Lexer::new(s)
-  } else {
-    panic!("file error");
-  };
-  let tokens = lexer.lex_all();
-  for i in tokens {
-    println!("{:?}", i);
+fn color(c: &Command) -> bool {
+  match c.color.as_str() {
+    "always" => true,
+    "never" => false,
+    _ => env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal(),
   }
 }
+
+fn report(c: &Command, source_map: &SourceMap, d: &Diagnostic) {
+  eprint!("{}", Renderer::new(color(c)).render(source_map, d));
+}
@@ -0,0 +1,100 @@
+use crate::bignum::BigInt;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// a value that can be used as a map key
+///
+/// floats and decimals are left out: `1.10d` and `1.1d` are equal but
+/// would hash differently, and NaN is not even equal to itself
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+  Nil,
+  Bool(bool),
+  Int(i64),
+  BigInt(Rc<BigInt>),
+  Str(Rc<str>),
+}
+
+impl Key {
+  pub fn from_value(v: &Value) -> Option<Key> {
+    match v {
+      Value::Nil => Option::Some(Key::Nil),
+      Value::Bool(b) => Option::Some(Key::Bool(*b)),
+      Value::Int(i) => Option::Some(Key::Int(*i)),
+      Value::BigInt(i) => Option::Some(Key::BigInt(i.clone())),
+      Value::Str(s) => Option::Some(Key::Str(s.clone())),
+      _ => Option::None,
+    }
+  }
+
+  pub fn to_value(&self) -> Value {
+    match self {
+      Key::Nil => Value::Nil,
+      Key::Bool(b) => Value::Bool(*b),
+      Key::Int(i) => Value::Int(*i),
+      Key::BigInt(i) => Value::BigInt(i.clone()),
+      Key::Str(s) => Value::Str(s.clone()),
+    }
+  }
+}
+
+/// a map iterating in the order its keys were first inserted
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+  entries: Vec<(Key, Value)>,
+  /// position of every key in `entries`
+  index: HashMap<Key, usize>,
+}
+
+impl Map {
+  pub fn new() -> Map {
+    Map::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn get(&self, key: &Key) -> Option<&Value> {
+    self.index.get(key).map(|&i| &self.entries[i].1)
+  }
+
+  /// updates the value of `key` in place, or appends it
+  pub fn insert(&mut self, key: Key, val: Value) {
+    match self.index.get(&key) {
+      Option::Some(&i) => self.entries[i].1 = val,
+      Option::None => {
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, val));
+      }
+    }
+  }
+
+  /// removes `key`, keeping the order of the others
+  pub fn remove(&mut self, key: &Key) -> Option<Value> {
+    let i = self.index.remove(key)?;
+    let (_, val) = self.entries.remove(i);
+    for (k, _) in &self.entries[i..] {
+      if let Option::Some(j) = self.index.get_mut(k) {
+        *j -= 1;
+      }
+    }
+    Option::Some(val)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &(Key, Value)> {
+    self.entries.iter()
+  }
+}
+
+impl PartialEq for Map {
+  // the order does not matter, the pairs do
+  fn eq(&self, other: &Map) -> bool {
+    self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Option::Some(v))
+  }
+}
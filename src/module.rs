@@ -0,0 +1,121 @@
+use crate::ast::File;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::source::SourceMap;
+use crate::value::Module;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// extension added to module paths that have none
+pub const EXTENSION: &str = "plum";
+
+/// a file being executed
+struct Frame {
+  /// canonical path, identifies the file
+  key: PathBuf,
+  /// path as it was resolved, shown in messages
+  path: PathBuf,
+}
+
+/// finds, parses and caches the modules a program imports
+#[derive(Default)]
+pub struct Loader {
+  /// every file loaded so far, the program included
+  pub source_map: SourceMap,
+  /// directories searched for modules not found next to the importing file
+  pub search_path: Vec<PathBuf>,
+  /// executed modules by canonical path
+  cache: HashMap<PathBuf, Rc<Module>>,
+  /// files being executed, importers first
+  loading: Vec<Frame>,
+}
+
+impl Loader {
+  pub fn new() -> Loader {
+    Loader::default()
+  }
+
+  /// finds the file `import name` refers to from the file being executed
+  ///
+  /// a relative path is looked up next to the importing file first, then in
+  /// every directory of the search path
+  pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+    let mut rel = PathBuf::from(name);
+    if rel.extension().is_none() {
+      rel.set_extension(EXTENSION);
+    }
+    if rel.is_absolute() {
+      return Option::Some(rel).filter(|p| p.is_file());
+    }
+    let dir = match self.loading.last() {
+      Option::Some(f) => f.path.parent().map(Path::to_path_buf).unwrap_or_default(),
+      Option::None => PathBuf::new(),
+    };
+    std::iter::once(&dir)
+      .chain(&self.search_path)
+      .map(|d| d.join(&rel))
+      .find(|p| p.is_file())
+  }
+
+  pub fn cached(&self, path: &Path) -> Option<Rc<Module>> {
+    self.cache.get(&key(path)).cloned()
+  }
+
+  pub fn insert(&mut self, path: &Path, module: Rc<Module>) {
+    self.cache.insert(key(path), module);
+  }
+
+  /// the files importing each other back to `path`, if loading it now
+  /// would close an import cycle
+  pub fn cycle(&self, path: &Path) -> Option<Vec<PathBuf>> {
+    let k = key(path);
+    let i = self.loading.iter().position(|f| f.key == k)?;
+    let mut ret: Vec<PathBuf> = self.loading[i..].iter().map(|f| f.path.clone()).collect();
+    ret.push(path.to_path_buf());
+    Option::Some(ret)
+  }
+
+  /// reads and parses a file, registering it in the source map
+  ///
+  /// returns the syntax errors, or the reason it cannot be read at position `0`
+  pub fn parse(&mut self, path: &Path) -> Result<Box<File>, Vec<(usize, String)>> {
+    let src = fs::read_to_string(path)
+      .map_err(|e| vec![(0, format!("cannot read {}: {}", path.display(), e))])?;
+    self.parse_source(path.display().to_string(), src)
+  }
+
+  /// parses source code called `name` in diagnostics, registering it in the
+  /// source map
+  pub fn parse_source(
+    &mut self,
+    name: String,
+    src: String,
+  ) -> Result<Box<File>, Vec<(usize, String)>> {
+    let base = self.source_map.add_file(name, src.clone());
+    let mut parser = Parser::new(Lexer::with_base(src, base));
+    let file = parser.parse_file();
+    if !parser.errors.is_empty() {
+      return Err(parser.errors);
+    }
+    Ok(file)
+  }
+
+  /// marks `path` as being executed until the matching `leave`
+  pub fn enter(&mut self, path: &Path) {
+    self.loading.push(Frame {
+      key: key(path),
+      path: path.to_path_buf(),
+    });
+  }
+
+  pub fn leave(&mut self) {
+    self.loading.pop();
+  }
+}
+
+/// the canonical form of `path`, or `path` itself if it does not exist
+fn key(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,266 @@
+use crate::error::*;
+use crate::interpreter::Interpreter;
+use crate::map::Map;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/// the number of arguments a native function takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+  Exact(usize),
+  Any,
+}
+
+impl Arity {
+  pub fn check(&self, name: &str, n: usize) -> Result<()> {
+    match *self {
+      Arity::Exact(m) if m != n => Err(RuntimeError::unlocated(
+        ErrorKind::Arity,
+        format!("`{}` expects {} arguments, got {}", name, m, n),
+      )),
+      _ => Ok(()),
+    }
+  }
+}
+
+pub type NativeFn = dyn Fn(&mut Interpreter, &Args) -> Result<Value>;
+
+/// a function implemented in Rust, see `Interpreter::register`
+pub struct Native {
+  pub name: String,
+  pub arity: Arity,
+  pub fun: Box<NativeFn>,
+}
+
+impl fmt::Debug for Native {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Native({})", self.name)
+  }
+}
+
+/// the arguments of a native function call
+///
+/// the typed getters fail with a type error naming the function, errors
+/// returned without a position are reported at the call
+pub struct Args {
+  name: String,
+  values: Vec<Value>,
+}
+
+impl Args {
+  pub fn new(name: &str, values: Vec<Value>) -> Args {
+    Args {
+      name: String::from(name),
+      values,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.values.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+
+  pub fn values(&self) -> &[Value] {
+    &self.values
+  }
+
+  /// the `i`th argument, an arity error if there are fewer
+  pub fn get(&self, i: usize) -> Result<&Value> {
+    self.values.get(i).ok_or_else(|| {
+      RuntimeError::unlocated(
+        ErrorKind::Arity,
+        format!(
+          "`{}` expects at least {} arguments, got {}",
+          self.name,
+          i + 1,
+          self.values.len()
+        ),
+      )
+    })
+  }
+
+  /// an error saying the `i`th argument is not what the function expects
+  pub fn error(&self, i: usize, expected: &str) -> RuntimeError {
+    let got = self.values.get(i).map_or("nothing", Value::type_name);
+    RuntimeError::unlocated(
+      ErrorKind::Type,
+      format!("`{}` expects {}, got {}", self.name, expected, got),
+    )
+  }
+
+  pub fn bool(&self, i: usize) -> Result<bool> {
+    match self.get(i)? {
+      Value::Bool(b) => Ok(*b),
+      _ => Err(self.error(i, "a bool")),
+    }
+  }
+
+  /// a 64-bit int, an overflow error for a larger one
+  pub fn int(&self, i: usize) -> Result<i64> {
+    match self.get(i)? {
+      Value::Int(v) => Ok(*v),
+      Value::BigInt(_) => Err(RuntimeError::unlocated(
+        ErrorKind::Overflow,
+        format!(
+          "argument {} of `{}` does not fit in a 64-bit int",
+          i + 1,
+          self.name
+        ),
+      )),
+      _ => Err(self.error(i, "an int")),
+    }
+  }
+
+  /// any number, converted to a float
+  pub fn float(&self, i: usize) -> Result<f64> {
+    self
+      .get(i)?
+      .to_f64()
+      .ok_or_else(|| self.error(i, "a number"))
+  }
+
+  pub fn str(&self, i: usize) -> Result<Rc<str>> {
+    match self.get(i)? {
+      Value::Str(s) => Ok(s.clone()),
+      _ => Err(self.error(i, "a string")),
+    }
+  }
+
+  pub fn list(&self, i: usize) -> Result<Rc<RefCell<Vec<Value>>>> {
+    match self.get(i)? {
+      Value::List(l) => Ok(l.clone()),
+      _ => Err(self.error(i, "a list")),
+    }
+  }
+
+  pub fn map(&self, i: usize) -> Result<Rc<RefCell<Map>>> {
+    match self.get(i)? {
+      Value::Map(m) => Ok(m.clone()),
+      _ => Err(self.error(i, "a map")),
+    }
+  }
+
+  /// a function, to be called with `Interpreter::call_value`
+  pub fn fun(&self, i: usize) -> Result<Value> {
+    match self.get(i)? {
+      v @ Value::Fun(_) | v @ Value::Native(_) => Ok(v.clone()),
+      _ => Err(self.error(i, "a function")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ast::Visitor;
+  use crate::lexer::Lexer;
+  use crate::parser::Parser;
+
+  /// an interpreter with natives taking each kind of argument
+  fn interp() -> Interpreter {
+    let mut interp = Interpreter::new();
+    interp.register("add", Arity::Exact(2), |_, args| {
+      Ok(Value::Int(args.int(0)? + args.int(1)?))
+    });
+    interp.register("count", Arity::Any, |_, args| {
+      Ok(Value::Int(args.len() as i64))
+    });
+    interp.register("kinds", Arity::Exact(6), |interp, args| {
+      let mut parts = vec![
+        Value::Bool(args.bool(0)?),
+        Value::Float(args.float(1)?),
+        Value::Str(args.str(2)?),
+        Value::Int(args.list(3)?.borrow().len() as i64),
+        Value::Int(args.map(4)?.borrow().len() as i64),
+      ];
+      parts[1] = interp.call_value(&args.fun(5)?, vec![parts[1].clone()])?;
+      Ok(Value::from(parts))
+    });
+    interp
+  }
+
+  fn eval(src: &str) -> Result<Value> {
+    let mut p = Parser::new(Lexer::new(String::from(src)));
+    let file = p.parse_file();
+    assert!(p.errors.is_empty(), "{:?}", p.errors);
+    interp().visit_file(&file)
+  }
+
+  /// the kind and message of the error of `src`, checking that it is
+  /// reported at the call
+  fn error(src: &str) -> (ErrorKind, String) {
+    let e = eval(src).unwrap_err();
+    // positions start at 1, 0 is unknown
+    assert_eq!((e.pos, e.end), (1, src.len() + 1), "{}", src);
+    (e.kind, e.msg)
+  }
+
+  #[test]
+  fn natives_get_typed_arguments() {
+    assert_eq!(eval("add(1, 2)").unwrap(), Value::Int(3));
+    assert_eq!(eval("count()").unwrap(), Value::Int(0));
+    assert_eq!(eval("count(1, nil, [])").unwrap(), Value::Int(3));
+    let v = eval(r#"kinds(true, 2, "s", [1, 2], {a: 1}, x => x * 2)"#).unwrap();
+    assert_eq!(v.to_string(), r#"[true, 4.0, "s", 2, 1]"#);
+    // variables shadow natives
+    assert_eq!(eval("var add = 1\nadd").unwrap(), Value::Int(1));
+  }
+
+  #[test]
+  fn arity_is_checked_before_the_call() {
+    assert_eq!(
+      error("add(1)"),
+      (
+        ErrorKind::Arity,
+        String::from("`add` expects 2 arguments, got 1")
+      )
+    );
+    assert_eq!(
+      error("add(1, 2, 3)"),
+      (
+        ErrorKind::Arity,
+        String::from("`add` expects 2 arguments, got 3")
+      )
+    );
+  }
+
+  #[test]
+  fn wrong_types_are_reported_at_the_call() {
+    assert_eq!(
+      error(r#"add(1, "2")"#),
+      (
+        ErrorKind::Type,
+        String::from("`add` expects an int, got string")
+      )
+    );
+    assert_eq!(
+      error("add(1, 99999999999999999999)"),
+      (
+        ErrorKind::Overflow,
+        String::from("argument 2 of `add` does not fit in a 64-bit int")
+      )
+    );
+    let cases = [
+      (r#"kinds(1, 2, "s", [], {}, len)"#, "a bool, got int"),
+      (
+        r#"kinds(true, "2", "s", [], {}, len)"#,
+        "a number, got string",
+      ),
+      ("kinds(true, 2, nil, [], {}, len)", "a string, got nil"),
+      (r#"kinds(true, 2, "s", {}, {}, len)"#, "a list, got map"),
+      (r#"kinds(true, 2, "s", [], [], len)"#, "a map, got list"),
+      (r#"kinds(true, 2, "s", [], {}, 1)"#, "a function, got int"),
+    ];
+    for (src, msg) in &cases {
+      assert_eq!(
+        error(src),
+        (ErrorKind::Type, format!("`kinds` expects {}", msg))
+      );
+    }
+  }
+}
@@ -1,11 +1,35 @@
+// the AST keeps its nodes boxed, lists of nodes included
+#![allow(clippy::vec_box)]
+
+use std::rc::Rc;
+
 use crate::ast::*;
 use crate::lexer::Lexer;
-use crate::token::{Token, LOWEST_PREC};
+use crate::token::{Segment, Token, LOWEST_PREC};
+
+/// parsing stops after this many errors
+const MAX_ERRORS: usize = 10;
 
 pub struct Parser {
   tok: Token,
   pos: usize,
+  /// position right after the current token
+  end: usize,
+  /// line of the current token
+  line: usize,
+  /// whether the previous token was a semicolon, i.e. a statement just ended
+  after_semi: bool,
   lexer: Lexer,
+  /// number of loops enclosing the current statement
+  loop_depth: usize,
+  /// number of blocks enclosing the current statement
+  block_depth: usize,
+  /// number of functions enclosing the current statement
+  fun_depth: usize,
+  /// set by `error`, the current statement must be synchronized
+  failed: bool,
+  /// line of the last reported error
+  error_line: usize,
   pub errors: Vec<(usize, String)>,
 }
 
@@ -14,7 +38,15 @@ impl Parser {
     let mut p = Parser {
       tok: Token::Illegal,
       pos: 0,
+      end: 0,
+      line: 0,
+      after_semi: false,
       lexer,
+      loop_depth: 0,
+      block_depth: 0,
+      fun_depth: 0,
+      failed: false,
+      error_line: 0,
       errors: Vec::new(),
     };
     p.next();
@@ -27,17 +59,80 @@ impl Parser {
   }
 
   fn next(&mut self) {
-    let t = self.lexer.lex();
+    self.after_semi = matches!(self.tok, Token::Semicolon);
+    let t = loop {
+      match self.lexer.lex() {
+        (Token::Comment(_), _) => continue,
+        t => break t,
+      }
+    };
     self.tok = t.0;
     self.pos = t.1;
+    self.end = self.lexer.end();
+    self.line = self.lexer.line();
+    let errors: Vec<(usize, String)> = self.lexer.errors.drain(..).collect();
+    for (pos, msg) in errors {
+      self.report(pos, msg);
+    }
   }
 
   fn tok_prec(&self) -> i32 {
     self.tok.prec()
   }
 
+  /// records a syntax error, the current statement will be synchronized
   fn error(&mut self, pos: usize, msg: String) {
-    self.errors.push((pos, msg))
+    self.failed = true;
+    self.report(pos, msg);
+  }
+
+  /// records an error, keeping only the first one of every line
+  fn report(&mut self, pos: usize, msg: String) {
+    if self.line == self.error_line || self.errors.len() > MAX_ERRORS {
+      return;
+    }
+    self.error_line = self.line;
+    if self.errors.len() == MAX_ERRORS {
+      self.errors.push((pos, String::from("too many errors")));
+    } else {
+      self.errors.push((pos, msg));
+    }
+  }
+
+  fn bailed_out(&self) -> bool {
+    self.errors.len() > MAX_ERRORS
+  }
+
+  /// skips the rest of a statement that failed to parse, up to and
+  /// including its semicolon or up to the '}' closing its block
+  fn sync_stmt(&mut self) {
+    let mut depth = 0;
+    loop {
+      match self.tok {
+        Token::Semicolon if depth == 0 => {
+          self.next();
+          return;
+        }
+        Token::Lbrace => depth += 1,
+        Token::Rbrace if depth == 0 => return,
+        Token::Rbrace => depth -= 1,
+        Token::Eof => return,
+        _ => (),
+      }
+      self.next();
+    }
+  }
+
+  /// the semicolon before a closing '}' may be omitted
+  fn expect_semi(&mut self) {
+    match self.tok {
+      Token::Semicolon => self.next(),
+      Token::Rbrace => (),
+      _ => self.error(
+        self.pos,
+        format!("expected `{}`, found `{}`", Token::Semicolon, self.tok),
+      ),
+    }
   }
 }
 
@@ -50,9 +145,9 @@ macro_rules! expect {
     } else {
       $self.error(
         $self.pos,
-        String::from(format!("expect {:?}, got {:?}", $tok, $self.tok)),
+        format!("expected `{}`, found `{}`", $tok, $self.tok),
       );
-      0
+      $self.pos
     }
   };
 }
@@ -62,57 +157,283 @@ impl Parser {
   fn parse_stmts(&mut self) -> Vec<Box<Stmt>> {
     let mut ret: Vec<Box<Stmt>> = vec![];
     loop {
-      if let Token::Eof = self.tok {
-        break;
+      ret.append(&mut self.parse_stmt_list());
+      match self.tok {
+        Token::Rbrace if !self.bailed_out() => {
+          self.error(self.pos, format!("unexpected `{}`", self.tok));
+          self.next();
+        }
+        _ => break,
+      }
+    }
+    ret
+  }
+
+  /// stmtList ::= stmt*;
+  fn parse_stmt_list(&mut self) -> Vec<Box<Stmt>> {
+    let mut ret: Vec<Box<Stmt>> = vec![];
+    loop {
+      match self.tok {
+        _ if self.bailed_out() => break,
+        Token::Rbrace | Token::Eof => break,
+        // empty statement
+        Token::Semicolon => self.next(),
+        _ => {
+          self.failed = false;
+          let pos = self.pos;
+          ret.push(self.parse_stmt());
+          if self.failed && !self.after_semi {
+            self.sync_stmt();
+          }
+          // every statement eats at least one token, or this never ends
+          if self.pos == pos && !matches!(self.tok, Token::Rbrace | Token::Eof) {
+            self.next();
+          }
+        }
       }
-      ret.push(self.parse_stmt());
     }
     ret
   }
 
   fn parse_stmt(&mut self) -> Box<Stmt> {
-    return match self.tok {
-      Token::Var | Token::Fun => self.parse_decl_stmt(),
+    match self.tok {
+      Token::Var => self.parse_decl_stmt(Option::None),
+      Token::Fun => self.parse_fun_stmt(),
+      Token::Export => self.parse_export_stmt(),
+      Token::Import => self.parse_import_stmt(),
+      Token::Return => self.parse_return_stmt(),
+      Token::Lbrace => {
+        let block = self.parse_block_stmt();
+        self.expect_semi();
+        Box::new(Stmt::Block(block))
+      }
+      Token::If => {
+        let stmt = self.parse_if_stmt();
+        self.expect_semi();
+        stmt
+      }
+      Token::While => self.parse_while_stmt(),
+      Token::Break | Token::Continue => self.parse_branch_stmt(),
       Token::Ident(_)
       | Token::Integer(_)
       | Token::Float(_)
+      | Token::Decimal(_)
       | Token::String(_)
+      | Token::Interp(_)
+      | Token::True
+      | Token::False
+      | Token::Nil
       | Token::Add
-      | Token::Sub => self.parse_simple_stmt(),
+      | Token::Sub
+      | Token::Exel
+      | Token::Lparen
+      | Token::Lbrack => self.parse_simple_stmt(Option::None),
       _ => {
-        self.next();
-        Box::new(Stmt::Bad(BadStmt {}))
+        let (from, to) = (self.pos, self.end);
+        self.error(from, format!("expected statement, found `{}`", self.tok));
+        if !matches!(self.tok, Token::Rbrace | Token::Eof) {
+          self.next();
+        }
+        Box::new(Stmt::Bad(BadStmt { from, to }))
       }
-    };
+    }
   }
 
-  fn parse_decl_stmt(&mut self) -> Box<Stmt> {
+  fn parse_decl_stmt(&mut self, export_pos: Option<usize>) -> Box<Stmt> {
     let decl: Box<Decl> = self.parse_decl();
-    expect!(self, Token::Semicolon);
-    Box::new(Stmt::Decl(DeclStmt { decl }))
+    self.expect_semi();
+    Box::new(Stmt::Decl(DeclStmt { export_pos, decl }))
   }
 
-  fn parse_simple_stmt(&mut self) -> Box<Stmt> {
-    let x = self.parse_expr();
-    match self.tok {
-      Token::Assign => {
-        let pos = self.pos;
+  /// exportStmt ::= 'export' (varDecl | funDecl);
+  fn parse_export_stmt(&mut self) -> Box<Stmt> {
+    let pos = expect!(self, Token::Export);
+    if self.block_depth > 0 {
+      self.report(pos, String::from("export is only allowed at the top level"));
+    }
+    if let Token::Var | Token::Fun = self.tok {
+      return self.parse_decl_stmt(Option::Some(pos));
+    }
+    let (from, to) = (self.pos, self.end);
+    self.error(
+      from,
+      format!(
+        "expected `var` or `fun` after `export`, found `{}`",
+        self.tok
+      ),
+    );
+    Box::new(Stmt::Bad(BadStmt { from, to }))
+  }
+
+  /// importStmt ::= 'import' (String | Ident) ('as' Ident)?;
+  fn parse_import_stmt(&mut self) -> Box<Stmt> {
+    let import_pos = expect!(self, Token::Import);
+    let path = match self.tok.clone() {
+      Token::String(_) => {
+        let (pos, end) = (self.pos, self.end);
         let tok = self.tok.clone();
         self.next();
-        let y = self.parse_expr();
-        expect!(self, Token::Semicolon);
-        return Box::from(Stmt::Assign(AssignStmt {
-          ptr: x,
-          pos,
-          tok,
-          val: y,
-        }));
+        Box::new(Expr::BasicLit(BasicLit { pos, end, tok }))
+      }
+      Token::Ident(name) => {
+        let pos = self.pos;
+        self.next();
+        Box::new(Expr::Ident(Ident { pos, name }))
+      }
+      _ => {
+        let (from, to) = (self.pos, self.end);
+        self.error(from, format!("expected module path, found `{}`", self.tok));
+        return Box::new(Stmt::Bad(BadStmt { from, to }));
       }
-      _ => (),
+    };
+    let alias = if let Token::As = self.tok {
+      self.next();
+      Option::Some(self.parse_ident())
+    } else {
+      Option::None
+    };
+    self.expect_semi();
+    Box::new(Stmt::Import(ImportStmt {
+      import_pos,
+      path,
+      alias,
+    }))
+  }
+
+  /// a function declaration, or an expression starting with a function literal
+  fn parse_fun_stmt(&mut self) -> Box<Stmt> {
+    let fun_pos = expect!(self, Token::Fun);
+    if let Token::Lparen = self.tok {
+      let x = self.parse_fun_lit(fun_pos);
+      let x = self.parse_primary_expr(Option::Some(x));
+      let x = self.parse_binary_expr(Option::Some(x), LOWEST_PREC + 1);
+      return self.parse_simple_stmt(Option::Some(x));
+    }
+    let decl = self.parse_fun_decl(fun_pos);
+    self.expect_semi();
+    Box::new(Stmt::Decl(DeclStmt {
+      export_pos: Option::None,
+      decl,
+    }))
+  }
+
+  fn parse_simple_stmt(&mut self, xx: Option<Box<Expr>>) -> Box<Stmt> {
+    let x = if let Option::Some(v) = xx {
+      v
+    } else {
+      self.parse_expr()
+    };
+    if let Token::Assign = self.tok {
+      let pos = self.pos;
+      let tok = self.tok.clone();
+      self.next();
+      let y = self.parse_expr();
+      self.expect_semi();
+      return Box::from(Stmt::Assign(AssignStmt {
+        ptr: x,
+        pos,
+        tok,
+        val: y,
+      }));
     }
-    expect!(self, Token::Semicolon);
+    self.expect_semi();
     Box::new(Stmt::Expr(ExprStmt { x }))
   }
+
+  /// returnStmt ::= 'return' expr?;
+  fn parse_return_stmt(&mut self) -> Box<Stmt> {
+    let pos = expect!(self, Token::Return);
+    if self.fun_depth == 0 {
+      self.report(pos, String::from("return is not in a function"));
+    }
+    let value = match self.tok {
+      Token::Semicolon | Token::Rbrace => Option::None,
+      _ => Option::Some(self.parse_expr()),
+    };
+    self.expect_semi();
+    Box::new(Stmt::Return(ReturnStmt { pos, value }))
+  }
+
+  /// ifStmt ::= 'if' expr blockStmt ('else' (ifStmt | blockStmt))?;
+  fn parse_if_stmt(&mut self) -> Box<Stmt> {
+    let if_pos = expect!(self, Token::If);
+    let cond = self.parse_expr();
+    let body = Box::new(self.parse_block_stmt());
+    let els = if let Token::Else = self.tok {
+      self.next();
+      match self.tok {
+        Token::If => Option::Some(self.parse_if_stmt()),
+        Token::Lbrace => Option::Some(Box::new(Stmt::Block(self.parse_block_stmt()))),
+        _ => {
+          self.error(
+            self.pos,
+            format!("expected `if` or block after `else`, found `{}`", self.tok),
+          );
+          Option::None
+        }
+      }
+    } else {
+      Option::None
+    };
+    Box::new(Stmt::If(IfStmt {
+      if_pos,
+      cond,
+      body,
+      els,
+    }))
+  }
+
+  /// whileStmt ::= 'while' expr blockStmt;
+  fn parse_while_stmt(&mut self) -> Box<Stmt> {
+    let while_pos = expect!(self, Token::While);
+    let cond = self.parse_expr();
+    self.loop_depth += 1;
+    let body = Box::new(self.parse_block_stmt());
+    self.loop_depth -= 1;
+    self.expect_semi();
+    Box::new(Stmt::While(WhileStmt {
+      while_pos,
+      cond,
+      body,
+    }))
+  }
+
+  /// branchStmt ::= 'break' | 'continue';
+  fn parse_branch_stmt(&mut self) -> Box<Stmt> {
+    let pos = self.pos;
+    let tok = self.tok.clone();
+    self.next();
+    if self.loop_depth == 0 {
+      self.report(pos, format!("{} is not in a loop", tok));
+    }
+    self.expect_semi();
+    Box::new(Stmt::Branch(BranchStmt { pos, tok }))
+  }
+
+  /// blockStmt ::= '{' stmtList '}';
+  fn parse_block_stmt(&mut self) -> BlockStmt {
+    let lbrace = self.pos;
+    if let Token::Lbrace = self.tok {
+      self.next();
+    } else {
+      // without its '{' the rest of the block is left to `sync_stmt`
+      expect!(self, Token::Lbrace);
+      return BlockStmt {
+        lbrace,
+        stmts: vec![],
+        rbrace: lbrace,
+      };
+    }
+    self.block_depth += 1;
+    let stmts = self.parse_stmt_list();
+    self.block_depth -= 1;
+    let rbrace = expect!(self, Token::Rbrace);
+    BlockStmt {
+      lbrace,
+      stmts,
+      rbrace,
+    }
+  }
 }
 
 impl Parser {
@@ -123,7 +444,15 @@ impl Parser {
         self.next();
         self.parse_var_decl(var_pos)
       }
-      _ => Box::new(Decl::Bad(BadDecl {})),
+      Token::Fun => {
+        let fun_pos = self.pos;
+        self.next();
+        self.parse_fun_decl(fun_pos)
+      }
+      _ => Box::new(Decl::Bad(BadDecl {
+        from: self.pos,
+        to: self.end,
+      })),
     }
   }
 
@@ -149,6 +478,49 @@ impl Parser {
       }))
     }
   }
+
+  /// funDecl ::= 'fun' Ident '(' paramList ')' blockStmt;
+  fn parse_fun_decl(&mut self, fun_pos: usize) -> Box<Decl> {
+    // 'fun' has been eaten
+    let name = self.parse_ident();
+    let lp_pos = expect!(self, Token::Lparen);
+    let params = self.parse_param_list();
+    let rp_pos = expect!(self, Token::Rparen);
+    let body = self.parse_fun_body(Parser::parse_block_stmt);
+    Box::new(Decl::Fun(FunDecl {
+      fun_pos,
+      name,
+      lp_pos,
+      params,
+      rp_pos,
+      body: Rc::new(body),
+    }))
+  }
+
+  /// parses the body of a function with `f`
+  fn parse_fun_body(&mut self, f: fn(&mut Parser) -> BlockStmt) -> BlockStmt {
+    // loops outside the function do not enclose its body
+    let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+    self.fun_depth += 1;
+    let body = f(self);
+    self.fun_depth -= 1;
+    self.loop_depth = loop_depth;
+    body
+  }
+
+  /// paramList ::= (Ident (',' Ident)* ','?)?;
+  fn parse_param_list(&mut self) -> Vec<Box<Ident>> {
+    let mut ret: Vec<Box<Ident>> = vec![];
+    while let Token::Ident(_) = self.tok {
+      ret.push(self.parse_ident());
+      if let Token::Comma = self.tok {
+        self.next(); // eat comma
+      } else {
+        break;
+      }
+    }
+    ret
+  }
 }
 
 /// expressions
@@ -174,28 +546,32 @@ impl Parser {
       if pr < prec {
         return x;
       }
+      let op_pos = self.pos;
       let op = self.tok.clone();
       self.next();
-      let y = self.parse_binary_expr(Option::None, prec + 1);
-      x = Box::from(Expr::Binary(BinaryExpr { x, op, y }))
+      let y = self.parse_binary_expr(Option::None, pr + 1);
+      x = Box::from(Expr::Binary(BinaryExpr { x, op_pos, op, y }))
     }
   }
 
   /// unaryExpr ::= op expr;
   fn parse_unary_expr(&mut self) -> Box<Expr> {
     match self.tok {
-      Token::Add | Token::Sub => {
+      Token::Add | Token::Sub | Token::Exel => {
+        let op_pos = self.pos;
         let op = self.tok.clone();
         self.next();
         let x = self.parse_unary_expr();
-        Box::from(Expr::Unary(UnaryExpr { op, x }))
+        Box::from(Expr::Unary(UnaryExpr { op_pos, op, x }))
       }
       _ => self.parse_primary_expr(Option::None),
     }
   }
 
   /// primaryExpr ::= operand
-  ///               | operand '(' exprList ')' # callExpr;
+  ///               | operand '(' exprList ')' # callExpr
+  ///               | primaryExpr '[' expr ']' # indexExpr
+  ///               | primaryExpr '.' Ident # selectorExpr;
   fn parse_primary_expr(&mut self, xx: Option<Box<Expr>>) -> Box<Expr> {
     let mut x = if let Option::Some(v) = xx {
       v
@@ -205,8 +581,13 @@ impl Parser {
     loop {
       match self.tok {
         Token::Lparen => x = self.parse_call_expr(Option::from(x)),
-        // Token::Lbrack
-        // Token::Lbrace
+        Token::Lbrack => x = self.parse_index_expr(x),
+        Token::Period => {
+          let period = self.pos;
+          self.next();
+          let sel = self.parse_ident();
+          x = Box::new(Expr::Selector(SelectorExpr { x, period, sel }))
+        }
         _ => return x,
       }
     }
@@ -214,35 +595,175 @@ impl Parser {
 
   /// operand ::= Ident
   ///           | basicLit
+  ///           | listLit
+  ///           | mapLit
   ///           | '(' expr ')';
   fn parse_operand(&mut self) -> Box<Expr> {
     match self.tok.clone() {
       Token::Ident(lit) => {
         let pos = self.pos;
         self.next();
-        Box::new(Expr::Ident(Ident {
-          pos,
-          name: lit.clone(),
-        }))
+        let id = Box::new(Ident { pos, name: lit });
+        if let Token::Arrow = self.tok {
+          return self.parse_arrow_fun(pos, vec![id]);
+        }
+        Box::new(Expr::Ident(*id))
       }
-      Token::Integer(_) | Token::Float(_) | Token::String(_) => {
-        let pos = self.pos;
+      Token::Fun => {
+        let fun_pos = self.pos;
+        self.next();
+        self.parse_fun_lit(fun_pos)
+      }
+      Token::Integer(_)
+      | Token::Float(_)
+      | Token::Decimal(_)
+      | Token::String(_)
+      | Token::True
+      | Token::False
+      | Token::Nil => {
+        let (pos, end) = (self.pos, self.end);
         let tok = self.tok.clone();
         self.next();
-        Box::new(Expr::BasicLit(BasicLit { pos, tok }))
+        Box::new(Expr::BasicLit(BasicLit { pos, end, tok }))
+      }
+      Token::Interp(segs) => {
+        let (pos, end) = (self.pos, self.end);
+        self.next();
+        let parts = segs
+          .into_iter()
+          .map(|seg| match seg {
+            Segment::Str(lit) => InterpPart::Str(lit),
+            Segment::Code(code, pos) => InterpPart::Expr(self.parse_interp_code(code, pos)),
+          })
+          .collect();
+        Box::new(Expr::Interp(InterpExpr { pos, end, parts }))
       }
       Token::Lparen => {
         let l_pos = self.pos;
         self.next();
+        // `() => expr`
+        if let Token::Rparen = self.tok {
+          self.next();
+          return self.parse_arrow_fun(l_pos, vec![]);
+        }
         let x = self.parse_expr();
+        // `(a, b) => expr`
+        if let Token::Comma = self.tok {
+          let xs = self.parse_expr_list(Option::Some(x));
+          expect!(self, Token::Rparen);
+          let params = xs.into_iter().map(|x| self.expr_to_param(*x)).collect();
+          return self.parse_arrow_fun(l_pos, params);
+        }
         let r_pos = expect!(self, Token::Rparen);
+        if let Token::Arrow = self.tok {
+          let param = self.expr_to_param(*x);
+          return self.parse_arrow_fun(l_pos, vec![param]);
+        }
         Box::new(Expr::Paren(ParenExpr { l_pos, x, r_pos }))
       }
+      Token::Lbrack => self.parse_list_lit(),
+      Token::Lbrace => self.parse_map_lit(),
       _ => {
-        self.next();
-        Box::new(Expr::Bad(BadExpr {}))
+        let (from, to) = (self.pos, self.end);
+        self.error(from, format!("expected expression, found `{}`", self.tok));
+        // leave the tokens ending the statement to `sync_stmt`
+        match self.tok {
+          Token::Semicolon | Token::Rparen | Token::Rbrace | Token::Eof => (),
+          _ => self.next(),
+        }
+        Box::new(Expr::Bad(BadExpr { from, to }))
+      }
+    }
+  }
+
+  /// funLit ::= 'fun' '(' paramList ')' blockStmt;
+  ///
+  /// unlike a function declaration, the value of an expression statement
+  /// ending the block is returned
+  fn parse_fun_lit(&mut self, pos: usize) -> Box<Expr> {
+    // 'fun' has been eaten
+    expect!(self, Token::Lparen);
+    let params = self.parse_param_list();
+    expect!(self, Token::Rparen);
+    let body = self.parse_fun_body(Parser::parse_lit_body);
+    Box::new(Expr::Fun(FunLit {
+      pos,
+      params,
+      body: Rc::new(body),
+    }))
+  }
+
+  /// the block of a function literal, which returns the value of a trailing
+  /// expression statement
+  fn parse_lit_body(&mut self) -> BlockStmt {
+    let mut body = self.parse_block_stmt();
+    match body.stmts.pop().map(|x| *x) {
+      Option::Some(Stmt::Expr(ExprStmt { x })) => {
+        body.stmts.push(Box::new(Stmt::Return(ReturnStmt {
+          pos: x.beg(),
+          value: Option::Some(x),
+        })))
       }
+      Option::Some(stmt) => body.stmts.push(Box::new(stmt)),
+      Option::None => (),
+    }
+    body
+  }
+
+  /// arrowFun ::= (Ident | '(' paramList ')') '=>' (expr | blockStmt);
+  fn parse_arrow_fun(&mut self, pos: usize, params: Vec<Box<Ident>>) -> Box<Expr> {
+    // the parameters have been eaten
+    expect!(self, Token::Arrow);
+    let body = self.parse_fun_body(|p| {
+      if let Token::Lbrace = p.tok {
+        return p.parse_lit_body();
+      }
+      let value = p.parse_expr();
+      let (beg, end) = (value.beg(), value.end());
+      BlockStmt {
+        lbrace: beg,
+        stmts: vec![Box::new(Stmt::Return(ReturnStmt {
+          pos: beg,
+          value: Option::Some(value),
+        }))],
+        rbrace: end - 1,
+      }
+    });
+    Box::new(Expr::Fun(FunLit {
+      pos,
+      params,
+      body: Rc::new(body),
+    }))
+  }
+
+  /// the parameter a parenthesized expression before `=>` stands for
+  fn expr_to_param(&mut self, x: Expr) -> Box<Ident> {
+    match x {
+      Expr::Ident(id) => Box::new(id),
+      x => {
+        self.error(x.beg(), String::from("expected parameter name"));
+        Box::new(Ident {
+          pos: x.beg(),
+          name: String::from("_"),
+        })
+      }
+    }
+  }
+
+  /// parses the code of a `${...}` at `pos` with a parser of its own
+  fn parse_interp_code(&mut self, code: String, pos: usize) -> Box<Expr> {
+    let mut p = Parser::new(Lexer::with_base(code, pos - 1));
+    let x = p.parse_expr();
+    if let Token::Semicolon = p.tok {
+      p.next();
+    }
+    if !matches!(p.tok, Token::Eof) {
+      p.error(p.pos, format!("unexpected `{}` in interpolation", p.tok));
+    }
+    for (pos, msg) in p.errors {
+      self.report(pos, msg);
     }
+    x
   }
 
   /// callExpr ::= operand '(' exprList ')';
@@ -253,7 +774,11 @@ impl Parser {
       self.parse_unary_expr()
     };
     let lp_pos = expect!(self, Token::Lparen);
-    let args = self.parse_expr_list(Option::None);
+    let args = if let Token::Rparen = self.tok {
+      vec![]
+    } else {
+      self.parse_expr_list(Option::None)
+    };
     let rp_pos = expect!(self, Token::Rparen);
     Box::from(Expr::Call(CallExpr {
       fun,
@@ -263,6 +788,59 @@ impl Parser {
     }))
   }
 
+  /// listLit ::= '[' exprList? ']';
+  fn parse_list_lit(&mut self) -> Box<Expr> {
+    let lbrack = expect!(self, Token::Lbrack);
+    let elts = if let Token::Rbrack = self.tok {
+      vec![]
+    } else {
+      self.parse_expr_list(Option::None)
+    };
+    let rbrack = expect!(self, Token::Rbrack);
+    Box::new(Expr::List(ListLit {
+      lbrack,
+      elts,
+      rbrack,
+    }))
+  }
+
+  /// mapLit ::= '{' (keyValue (',' keyValue)* ','?)? '}';
+  /// keyValue ::= expr ':' expr;
+  fn parse_map_lit(&mut self) -> Box<Expr> {
+    let lbrace = expect!(self, Token::Lbrace);
+    let mut elts = vec![];
+    while !matches!(self.tok, Token::Rbrace | Token::Eof) {
+      let key = self.parse_expr();
+      let colon = expect!(self, Token::Colon);
+      let value = self.parse_expr();
+      elts.push(KeyValueExpr { key, colon, value });
+      if let Token::Comma = self.tok {
+        self.next();
+      } else {
+        break;
+      }
+    }
+    let rbrace = expect!(self, Token::Rbrace);
+    Box::new(Expr::Map(MapLit {
+      lbrace,
+      elts,
+      rbrace,
+    }))
+  }
+
+  /// indexExpr ::= primaryExpr '[' expr ']';
+  fn parse_index_expr(&mut self, x: Box<Expr>) -> Box<Expr> {
+    let lbrack = expect!(self, Token::Lbrack);
+    let index = self.parse_expr();
+    let rbrack = expect!(self, Token::Rbrack);
+    Box::new(Expr::Index(IndexExpr {
+      x,
+      lbrack,
+      index,
+      rbrack,
+    }))
+  }
+
   /// exprList ::= expr (',' expr)* ','?;
   fn parse_expr_list(&mut self, xx: Option<Box<Expr>>) -> Vec<Box<Expr>> {
     let x = if let Option::Some(v) = xx {
@@ -273,6 +851,9 @@ impl Parser {
     let mut ret: Vec<Box<Expr>> = vec![x];
     while let Token::Comma = self.tok {
       self.next(); // eat comma
+      if let Token::Rparen | Token::Rbrack = self.tok {
+        break;
+      }
       ret.push(self.parse_expr())
     }
     ret
@@ -285,10 +866,104 @@ impl Parser {
       self.next();
       (pos, lit)
     } else {
-      self.error(self.pos, String::from("expect Identifier"));
-      self.next();
-      (0, String::from("_"))
+      self.error(
+        self.pos,
+        format!("expected identifier, found `{}`", self.tok),
+      );
+      (self.pos, String::from("_"))
     };
     Box::from(Ident { pos, name })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn errors(src: &str) -> Vec<String> {
+    let mut p = Parser::new(Lexer::new(String::from(src)));
+    p.parse_file();
+    p.errors.into_iter().map(|(_, msg)| msg).collect()
+  }
+
+  #[test]
+  fn stray_closing_paren() {
+    assert_eq!(
+      errors("var a = 1\n)\nvar b = 2\n"),
+      ["expected statement, found `)`"]
+    );
+  }
+
+  #[test]
+  fn call_closed_on_next_line() {
+    assert_eq!(errors("println(1\n)\n").len(), 2);
+  }
+
+  #[test]
+  fn else_on_its_own_line() {
+    assert_eq!(
+      errors("if true {\n}\nelse {\n}\n"),
+      ["expected statement, found `else`"]
+    );
+  }
+
+  #[test]
+  fn line_starting_with_operator() {
+    assert_eq!(
+      errors("var a = 1\n* 2\n"),
+      ["expected statement, found `*`"]
+    );
+  }
+
+  #[test]
+  fn illegal_character_at_line_start() {
+    assert_eq!(
+      errors("var a = 1\n$\nvar b = 2\n"),
+      ["illegal character `$`"]
+    );
+  }
+
+  #[test]
+  fn one_error_per_line() {
+    assert_eq!(errors("var a = (1\nvar b = 1 1\nvar c = 1 1\n").len(), 3);
+  }
+
+  #[test]
+  fn errors_are_capped() {
+    let src = ")\n".repeat(100);
+    let errors = errors(&src);
+    assert_eq!(errors.len(), MAX_ERRORS + 1);
+    assert_eq!(errors[MAX_ERRORS], "too many errors");
+  }
+
+  #[test]
+  fn return_outside_function() {
+    assert_eq!(errors("return 1\n"), ["return is not in a function"]);
+    assert_eq!(errors("{ return }\n"), ["return is not in a function"]);
+    assert!(errors("fun f() { while true { if true { return 1 } } }\n").is_empty());
+    assert!(errors("var f = x => { return x }\n").is_empty());
+  }
+
+  #[test]
+  fn break_outside_loop() {
+    assert_eq!(errors("break\n"), ["break is not in a loop"]);
+    assert_eq!(
+      errors("while true { fun f() { continue } }\n"),
+      ["continue is not in a loop"]
+    );
+  }
+
+  #[test]
+  fn errors_inside_interpolations() {
+    let src = "var a = \"x ${1 +} y\"\nvar b = \"${1 2}\"\n";
+    let mut p = Parser::new(Lexer::new(String::from(src)));
+    p.parse_file();
+    assert_eq!(
+      p.errors,
+      [
+        (17, String::from("expected expression, found `EOF`")),
+        (35, String::from("unexpected `2` in interpolation")),
+      ]
+    );
+  }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+/// a source file registered in a `SourceMap`
+pub struct SourceFile {
+  pub name: String,
+  pub src: String,
+  /// positions of the file are `base + 1 ..= base + len + 1`
+  pub base: usize,
+  len: usize,
+  /// character index of the first character of every line
+  lines: Vec<usize>,
+}
+
+/// maps the offsets stored in tokens and AST nodes back to files, lines and columns
+#[derive(Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+}
+
+/// a human readable source position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+  pub file: String,
+  /// 1-based line, `0` if the position is unknown
+  pub line: usize,
+  /// 1-based column counted in characters
+  pub column: usize,
+}
+
+/// drops a byte order mark at the start and turns `\r\n` into `\n`,
+/// the lexer and the source map must see the same text for positions to agree
+pub fn normalize(src: &str) -> String {
+  src
+    .strip_prefix('\u{feff}')
+    .unwrap_or(src)
+    .replace("\r\n", "\n")
+}
+
+impl SourceMap {
+  pub fn new() -> SourceMap {
+    SourceMap { files: vec![] }
+  }
+
+  /// registers a file and returns the base its lexer must add to every offset
+  pub fn add_file(&mut self, name: String, src: String) -> usize {
+    let src = normalize(&src);
+    let base = match self.files.last() {
+      Option::Some(f) => f.base + f.len + 1,
+      Option::None => 0,
+    };
+    let mut lines = vec![0];
+    let mut len = 0;
+    for (i, ch) in src.chars().enumerate() {
+      if ch == '\n' {
+        lines.push(i + 1);
+      }
+      len += 1;
+    }
+    self.files.push(SourceFile {
+      name,
+      src,
+      base,
+      len,
+      lines,
+    });
+    base
+  }
+
+  pub fn file(&self, pos: usize) -> Option<&SourceFile> {
+    if pos == 0 {
+      return Option::None;
+    }
+    let i = self.files.partition_point(|f| f.base < pos);
+    if i == 0 {
+      Option::None
+    } else {
+      Option::Some(&self.files[i - 1])
+    }
+  }
+
+  pub fn position(&self, pos: usize) -> Position {
+    match self.file(pos) {
+      Option::Some(f) => {
+        let (line, column) = f.line_col(pos);
+        Position {
+          file: f.name.clone(),
+          line,
+          column,
+        }
+      }
+      Option::None => Position {
+        file: String::from("-"),
+        line: 0,
+        column: 0,
+      },
+    }
+  }
+}
+
+impl SourceFile {
+  /// 1-based line and column of a position inside this file
+  pub fn line_col(&self, pos: usize) -> (usize, usize) {
+    let idx = (pos - self.base - 1).min(self.len);
+    let line = self.lines.partition_point(|&start| start <= idx);
+    (line, idx - self.lines[line - 1] + 1)
+  }
+}
+
+impl fmt::Display for Position {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.line == 0 {
+      write!(f, "{}", self.file)
+    } else {
+      write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+  }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum Token {
@@ -9,7 +9,11 @@ pub enum Token {
   Ident(String),
   Integer(String),
   Float(String),
+  /// decimal literal, without its `d` suffix
+  Decimal(String),
   String(String),
+  /// string literal containing `${...}`
+  Interp(Vec<Segment>),
 
   Add, // +
   Sub, // -
@@ -17,7 +21,12 @@ pub enum Token {
   Div, // /
   Rem, // %
 
+  AddWrap, // +%
+  SubWrap, // -%
+  MulWrap, // *%
+
   Assign, // =
+  Arrow,  // =>
 
   Lor,  // ||
   Land, // &&
@@ -39,12 +48,31 @@ pub enum Token {
   Comma,     // ,
   Semicolon, // ;
   Colon,     // :
+  Period,    // .
   Exel,      // !
 
   Fun,
   Var,
   Import,
+  Export,
+  As,
   Return,
+  If,
+  Else,
+  While,
+  Break,
+  Continue,
+  True,
+  False,
+  Nil,
+}
+
+/// a piece of an interpolated string literal
+#[derive(Debug, Clone)]
+pub enum Segment {
+  Str(String),
+  /// source code between `${` and `}`, and its position
+  Code(String, usize),
 }
 
 pub const LOWEST_PREC: i32 = 0;
@@ -57,9 +85,76 @@ impl Token {
       Token::Lor => 1,
       Token::Land => 2,
       Token::Eql | Token::Neq | Token::Gtr | Token::Geq | Token::Lss | Token::Leq => 3,
-      Token::Add | Token::Sub => 4,
-      Token::Mul | Token::Div | Token::Rem => 5,
+      Token::Add | Token::Sub | Token::AddWrap | Token::SubWrap => 4,
+      Token::Mul | Token::Div | Token::Rem | Token::MulWrap => 5,
       _ => LOWEST_PREC,
     }
   }
 }
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let s = match self {
+      Token::Illegal => "ILLEGAL",
+      Token::Comment(lit) => return write!(f, "{}", lit),
+      Token::Eof => "EOF",
+      Token::Ident(lit) | Token::Integer(lit) | Token::Float(lit) => return write!(f, "{}", lit),
+      Token::Decimal(lit) => return write!(f, "{}d", lit),
+      Token::String(lit) => return write!(f, "{:?}", lit),
+      Token::Interp(segs) => {
+        write!(f, "\"")?;
+        for seg in segs {
+          match seg {
+            Segment::Str(lit) => write!(f, "{}", lit.escape_debug())?,
+            Segment::Code(code, _) => write!(f, "${{{}}}", code)?,
+          }
+        }
+        return write!(f, "\"");
+      }
+      Token::Add => "+",
+      Token::Sub => "-",
+      Token::Mul => "*",
+      Token::Div => "/",
+      Token::Rem => "%",
+      Token::AddWrap => "+%",
+      Token::SubWrap => "-%",
+      Token::MulWrap => "*%",
+      Token::Assign => "=",
+      Token::Arrow => "=>",
+      Token::Lor => "||",
+      Token::Land => "&&",
+      Token::Lss => "<",
+      Token::Leq => "<=",
+      Token::Gtr => ">",
+      Token::Geq => ">=",
+      Token::Eql => "==",
+      Token::Neq => "!=",
+      Token::Lparen => "(",
+      Token::Rparen => ")",
+      Token::Lbrack => "[",
+      Token::Rbrack => "]",
+      Token::Lbrace => "{",
+      Token::Rbrace => "}",
+      Token::Comma => ",",
+      Token::Semicolon => ";",
+      Token::Colon => ":",
+      Token::Period => ".",
+      Token::Exel => "!",
+      Token::Fun => "fun",
+      Token::Var => "var",
+      Token::Import => "import",
+      Token::Export => "export",
+      Token::As => "as",
+      Token::Return => "return",
+      Token::If => "if",
+      Token::Else => "else",
+      Token::While => "while",
+      Token::Break => "break",
+      Token::Continue => "continue",
+      Token::True => "true",
+      Token::False => "false",
+      Token::Nil => "nil",
+    };
+    write!(f, "{}", s)
+  }
+}
@@ -0,0 +1,354 @@
+use crate::ast::*;
+use crate::bignum::BigInt;
+use crate::decimal::Decimal;
+use crate::map::Map;
+use crate::native::Native;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::thread::LocalKey;
+
+/// variables of one block or function frame, shared with the closures that capture it
+pub type Scope = Rc<RefCell<HashMap<String, Value>>>;
+
+/// a runtime value
+#[derive(Debug, Clone)]
+pub enum Value {
+  Nil,
+  Bool(bool),
+  Int(i64),
+  /// an int that does not fit in an `i64`
+  BigInt(Rc<BigInt>),
+  Float(f64),
+  Decimal(Rc<Decimal>),
+  Str(Rc<str>),
+  /// lists are shared, not copied, by assignments and calls
+  List(Rc<RefCell<Vec<Value>>>),
+  Map(Rc<RefCell<Map>>),
+  Fun(Rc<Function>),
+  /// a function implemented in Rust
+  Native(Rc<Native>),
+  Module(Rc<Module>),
+}
+
+/// a user-defined function
+pub struct Function {
+  pub name: String,
+  pub params: Vec<String>,
+  pub body: Rc<BlockStmt>,
+  /// scopes visible where the function was declared
+  pub env: Vec<Scope>,
+}
+
+/// an imported module
+#[derive(Debug)]
+pub struct Module {
+  pub name: String,
+  /// values of the exported top-level names once the module has run
+  pub exports: HashMap<String, Value>,
+}
+
+impl fmt::Debug for Function {
+  // the captured scopes usually contain the function itself
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Function({})", self.name)
+  }
+}
+
+impl Value {
+  pub fn is_callable(&self) -> bool {
+    matches!(self, Value::Fun(_) | Value::Native(_))
+  }
+
+  /// `nil` and `false` are false, every other value is true
+  pub fn truthy(&self) -> bool {
+    !matches!(self, Value::Nil | Value::Bool(false))
+  }
+
+  pub fn type_name(&self) -> &'static str {
+    match self {
+      Value::Nil => "nil",
+      Value::Bool(_) => "bool",
+      Value::Int(_) | Value::BigInt(_) => "int",
+      Value::Float(_) => "float",
+      Value::Decimal(_) => "decimal",
+      Value::Str(_) => "string",
+      Value::List(_) => "list",
+      Value::Map(_) => "map",
+      Value::Fun(_) | Value::Native(_) => "function",
+      Value::Module(_) => "module",
+    }
+  }
+
+  /// like `to_string`, with strings quoted, as they are shown inside lists and maps
+  pub fn repr(&self) -> String {
+    match self {
+      Value::Str(s) => format!("{:?}", s),
+      v => v.to_string(),
+    }
+  }
+
+  pub fn is_number(&self) -> bool {
+    matches!(
+      self,
+      Value::Int(_) | Value::BigInt(_) | Value::Float(_) | Value::Decimal(_)
+    )
+  }
+
+  pub fn to_big(&self) -> Option<BigInt> {
+    match self {
+      Value::Int(i) => Option::Some(BigInt::from(*i)),
+      Value::BigInt(i) => Option::Some((**i).clone()),
+      _ => Option::None,
+    }
+  }
+
+  /// ints and decimals as a decimal, floats are not exact and are left out
+  pub fn to_decimal(&self) -> Option<Decimal> {
+    match self {
+      Value::Decimal(d) => Option::Some((**d).clone()),
+      _ => self.to_big().map(Decimal::from),
+    }
+  }
+
+  pub fn to_f64(&self) -> Option<f64> {
+    match self {
+      Value::Int(i) => Option::Some(*i as f64),
+      Value::BigInt(i) => Option::Some(i.to_f64()),
+      Value::Float(f) => Option::Some(*f),
+      Value::Decimal(d) => Option::Some(d.to_f64()),
+      _ => Option::None,
+    }
+  }
+}
+
+/// compares two numbers of any type exactly, `None` if one of them is not a
+/// number or is NaN
+///
+/// decimals are the exception, they are compared with floats as floats
+pub fn num_cmp(x: &Value, y: &Value) -> Option<Ordering> {
+  match (x, y) {
+    (Value::Int(a), Value::Int(b)) => Option::Some(a.cmp(b)),
+    (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+    (Value::Int(a), Value::Float(b)) => cmp_int_float(*a, *b),
+    (Value::Float(a), Value::Int(b)) => cmp_int_float(*b, *a).map(Ordering::reverse),
+    (Value::BigInt(a), Value::Float(b)) => cmp_big_float(a, *b),
+    (Value::Float(a), Value::BigInt(b)) => cmp_big_float(b, *a).map(Ordering::reverse),
+    (Value::Float(_), _) | (_, Value::Float(_)) => x.to_f64()?.partial_cmp(&y.to_f64()?),
+    (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+      Option::Some(x.to_decimal()?.cmp(&y.to_decimal()?))
+    }
+    _ => Option::Some(x.to_big()?.cmp(&y.to_big()?)),
+  }
+}
+
+fn cmp_big_float(i: &BigInt, f: f64) -> Option<Ordering> {
+  if f.is_nan() {
+    return Option::None;
+  }
+  match BigInt::from_f64(f) {
+    Option::Some(t) => Option::Some(i.cmp(&t).then(frac_cmp(f))),
+    // infinite
+    Option::None if f > 0.0 => Option::Some(Ordering::Less),
+    Option::None => Option::Some(Ordering::Greater),
+  }
+}
+
+/// how the integer part of `f` compares to `f`
+fn frac_cmp(f: f64) -> Ordering {
+  let t = f.trunc();
+  if f > t {
+    Ordering::Less
+  } else if f < t {
+    Ordering::Greater
+  } else {
+    Ordering::Equal
+  }
+}
+
+/// compares an int with a float exactly, without rounding the int to a float first
+pub fn cmp_int_float(i: i64, f: f64) -> Option<Ordering> {
+  // 2^63, the first float above every i64
+  const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+  if f.is_nan() {
+    return Option::None;
+  }
+  if f >= LIMIT {
+    return Option::Some(Ordering::Less);
+  }
+  if f < -LIMIT {
+    return Option::Some(Ordering::Greater);
+  }
+  Option::Some(i.cmp(&(f.trunc() as i64)).then(frac_cmp(f)))
+}
+
+thread_local! {
+  /// lists and maps being formatted, by address
+  static FORMATTING: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+  /// pairs of lists or maps being compared, by address
+  static COMPARING: RefCell<HashSet<(usize, usize)>> = RefCell::new(HashSet::new());
+}
+
+/// marks a list or map as being visited until dropped, so that one that
+/// contains itself is not visited forever
+struct Visit<K: 'static + Eq + Hash + Copy> {
+  set: &'static LocalKey<RefCell<HashSet<K>>>,
+  key: K,
+}
+
+impl<K: 'static + Eq + Hash + Copy> Visit<K> {
+  /// `None` if `key` is already being visited, it came around again
+  fn enter(set: &'static LocalKey<RefCell<HashSet<K>>>, key: K) -> Option<Visit<K>> {
+    if set.with(|s| s.borrow_mut().insert(key)) {
+      Option::Some(Visit { set, key })
+    } else {
+      Option::None
+    }
+  }
+}
+
+impl<K: 'static + Eq + Hash + Copy> Drop for Visit<K> {
+  fn drop(&mut self) {
+    let key = self.key;
+    self.set.with(|s| s.borrow_mut().remove(&key));
+  }
+}
+
+/// compares two lists or maps, a pair that comes around again while it is
+/// being compared is taken as equal
+fn eq_shared<T: PartialEq>(x: &Rc<RefCell<T>>, y: &Rc<RefCell<T>>) -> bool {
+  if Rc::ptr_eq(x, y) {
+    return true;
+  }
+  let key = (Rc::as_ptr(x) as usize, Rc::as_ptr(y) as usize);
+  match Visit::enter(&COMPARING, key) {
+    Option::Some(_visit) => *x.borrow() == *y.borrow(),
+    Option::None => true,
+  }
+}
+
+impl PartialEq for Value {
+  fn eq(&self, other: &Value) -> bool {
+    match (self, other) {
+      (Value::Nil, Value::Nil) => true,
+      (Value::Bool(x), Value::Bool(y)) => x == y,
+      (x, y) if x.is_number() && y.is_number() => num_cmp(x, y) == Option::Some(Ordering::Equal),
+      (Value::Str(x), Value::Str(y)) => x == y,
+      (Value::List(x), Value::List(y)) => eq_shared(x, y),
+      (Value::Map(x), Value::Map(y)) => eq_shared(x, y),
+      (Value::Fun(x), Value::Fun(y)) => Rc::ptr_eq(x, y),
+      (Value::Native(x), Value::Native(y)) => Rc::ptr_eq(x, y),
+      (Value::Module(x), Value::Module(y)) => Rc::ptr_eq(x, y),
+      _ => false,
+    }
+  }
+}
+
+impl From<bool> for Value {
+  fn from(b: bool) -> Value {
+    Value::Bool(b)
+  }
+}
+
+impl From<i64> for Value {
+  fn from(i: i64) -> Value {
+    Value::Int(i)
+  }
+}
+
+impl From<BigInt> for Value {
+  /// an `Int` when it fits
+  fn from(i: BigInt) -> Value {
+    match i.to_i64() {
+      Option::Some(i) => Value::Int(i),
+      Option::None => Value::BigInt(Rc::new(i)),
+    }
+  }
+}
+
+impl From<Decimal> for Value {
+  fn from(d: Decimal) -> Value {
+    Value::Decimal(Rc::new(d))
+  }
+}
+
+impl From<f64> for Value {
+  fn from(f: f64) -> Value {
+    Value::Float(f)
+  }
+}
+
+impl From<Vec<Value>> for Value {
+  fn from(l: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(l)))
+  }
+}
+
+impl From<Map> for Value {
+  fn from(m: Map) -> Value {
+    Value::Map(Rc::new(RefCell::new(m)))
+  }
+}
+
+impl From<&str> for Value {
+  fn from(s: &str) -> Value {
+    Value::Str(Rc::from(s))
+  }
+}
+
+impl From<String> for Value {
+  fn from(s: String) -> Value {
+    Value::Str(Rc::from(s))
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Nil => write!(f, "nil"),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Int(i) => write!(f, "{}", i),
+      Value::BigInt(i) => write!(f, "{}", i),
+      // keep a trailing `.0` so floats stay distinguishable from ints
+      Value::Float(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{:.1}", x),
+      Value::Float(x) => write!(f, "{}", x),
+      Value::Decimal(d) => write!(f, "{}", d),
+      Value::Str(s) => write!(f, "{}", s),
+      Value::List(l) => {
+        let _visit = match Visit::enter(&FORMATTING, Rc::as_ptr(l) as usize) {
+          Option::Some(v) => v,
+          Option::None => return write!(f, "[...]"),
+        };
+        write!(f, "[")?;
+        for (i, v) in l.borrow().iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}", v.repr())?;
+        }
+        write!(f, "]")
+      }
+      Value::Map(m) => {
+        let _visit = match Visit::enter(&FORMATTING, Rc::as_ptr(m) as usize) {
+          Option::Some(v) => v,
+          Option::None => return write!(f, "{{...}}"),
+        };
+        write!(f, "{{")?;
+        for (i, (k, v)) in m.borrow().iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}: ", k.to_value().repr())?;
+          write!(f, "{}", v.repr())?;
+        }
+        write!(f, "}}")
+      }
+      Value::Fun(fun) => write!(f, "<fun {}>", fun.name),
+      Value::Native(fun) => write!(f, "<native fun {}>", fun.name),
+      Value::Module(m) => write!(f, "<module {}>", m.name),
+    }
+  }
+}